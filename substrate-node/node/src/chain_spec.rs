@@ -1,7 +1,7 @@
 use sp_core::{Pair, Public, sr25519};
 use node_template_runtime::{
 	AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig,
-	SudoConfig, SystemConfig, WASM_BINARY, Signature
+	SudoConfig, SystemConfig, VaultModuleConfig, LiquidatorAdapterConfig, LiquidatorConfig, OracleConfig, WASM_BINARY, Signature
 };
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_finality_grandpa::AuthorityId as GrandpaId;
@@ -153,5 +153,18 @@ fn testnet_genesis(
 			// Assign network admin rights.
 			key: root_key,
 		}),
+		pallet_vault: Some(VaultModuleConfig {
+			rtokens: vec![],
+		}),
+		pallet_liquidator_adapter: Some(LiquidatorAdapterConfig {
+			strategies: vec![],
+		}),
+		pallet_liquidator: Some(LiquidatorConfig {
+			finders: vec![],
+			finders_only: false,
+		}),
+		pallet_oracle: Some(OracleConfig {
+			feeders: vec![],
+		}),
 	}
 }
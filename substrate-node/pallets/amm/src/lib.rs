@@ -0,0 +1,488 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Constant product market maker.
+//!
+//! Every pool holds two assets whose reserves keep `x * y = k` across swaps, minus the swap fee
+//! which stays in the pool for liquidity providers. Liquidity is tracked with an LP token per pool.
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+use sp_std::prelude::*;
+use sp_std::convert::TryInto;
+use frame_support::{ensure, transactional, dispatch::DispatchResult};
+use codec::{Encode, Decode};
+use sp_core::U256;
+use sp_runtime::{
+    traits::{AccountIdConversion, CheckedAdd, Zero},
+    PerThing, Permill, RuntimeDebug, SaturatedConversion,
+};
+use frame_support::traits::tokens::fungibles::{Inspect, Mutate, Transfer};
+
+/// Creates the LP token of a pool on behalf of the AMM.
+pub trait LpTokenFactory<AccountId, AssetId, Balance> {
+    /// Create asset `id` owned by `owner`.
+    fn create(id: AssetId, owner: &AccountId, min_balance: Balance) -> DispatchResult;
+}
+
+impl<AccountId, AssetId, Balance> LpTokenFactory<AccountId, AssetId, Balance> for () {
+    fn create(_: AssetId, _: &AccountId, _: Balance) -> DispatchResult {
+        Err("LP token creation is not supported".into())
+    }
+}
+
+/// Reserves of a pool, ordered by asset id.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Default, RuntimeDebug)]
+pub struct Pool<AssetId, Balance> {
+    /// Asset minted to liquidity providers
+    pub lp_asset_id: AssetId,
+    /// Reserve of the lower asset id
+    pub reserve_0: Balance,
+    /// Reserve of the higher asset id
+    pub reserve_1: Balance,
+}
+
+impl<AssetId: Ord, Balance: Copy> Pool<AssetId, Balance> {
+    /// Reserves of `asset_in` and of the other asset.
+    fn reserves(&self, asset_in: &AssetId, asset_out: &AssetId) -> (Balance, Balance) {
+        if asset_in < asset_out {
+            (self.reserve_0, self.reserve_1)
+        } else {
+            (self.reserve_1, self.reserve_0)
+        }
+    }
+
+    fn set_reserves(&mut self, asset_in: &AssetId, asset_out: &AssetId, reserve_in: Balance, reserve_out: Balance) {
+        if asset_in < asset_out {
+            self.reserve_0 = reserve_in;
+            self.reserve_1 = reserve_out;
+        } else {
+            self.reserve_1 = reserve_in;
+            self.reserve_0 = reserve_out;
+        }
+    }
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+    use super::*;
+    use frame_support::{pallet_prelude::*, PalletId};
+    use frame_system::pallet_prelude::*;
+    use codec::HasCompact;
+    use sp_runtime::traits::{AtLeast32BitUnsigned, MaybeSerializeDeserialize, Member};
+
+    #[pallet::config]
+    pub trait Config: frame_system::Config {
+        type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+        type Balance: Member + Parameter + AtLeast32BitUnsigned + Default + Copy + MaybeSerializeDeserialize;
+
+        type AssetId: Member + Parameter + Ord + Default + Copy + HasCompact + MaybeSerializeDeserialize;
+
+        /// The AMM's pallet id, used for deriving the account holding the pool reserves.
+        #[pallet::constant]
+        type PalletId: Get<PalletId>;
+
+        type Currencies: Inspect<Self::AccountId, AssetId = Self::AssetId, Balance = Self::Balance>
+            + Mutate<Self::AccountId>
+            + Transfer<Self::AccountId>;
+
+        /// Origin allowed to create pools.
+        type CreateOrigin: EnsureOrigin<Self::Origin>;
+
+        /// Creates LP tokens owned by the AMM.
+        type LpTokenFactory: LpTokenFactory<Self::AccountId, Self::AssetId, Self::Balance>;
+
+        /// Share of every swap input kept by the pool.
+        #[pallet::constant]
+        type SwapFee: Get<Permill>;
+
+        /// Maximum number of assets in a swap route.
+        #[pallet::constant]
+        type MaxPathLength: Get<u32>;
+    }
+
+    #[pallet::pallet]
+    #[pallet::generate_store(pub(super) trait Store)]
+    pub struct Pallet<T>(_);
+
+    /// Pools by their pair of assets, lower asset id first
+    #[pallet::storage]
+    #[pallet::getter(fn pool)]
+    pub type Pools<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AssetId,
+        Blake2_128Concat,
+        T::AssetId,
+        Pool<T::AssetId, T::Balance>,
+        OptionQuery,
+    >;
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn integrity_test() {
+            // A fee of the whole input leaves nothing to trade and nothing to price a purchase by
+            assert!(T::SwapFee::get() < Permill::one(), "SwapFee must be below 100%");
+        }
+    }
+
+    #[pallet::event]
+    #[pallet::metadata(T::AccountId = "AccountId", T::AssetId = "AssetId", T::Balance = "Balance")]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
+        /// Event emitted when a pool is created [asset_0, asset_1, lp_asset_id]
+        PoolCreated(T::AssetId, T::AssetId, T::AssetId),
+        /// Event emitted when liquidity is added [who, asset_0, asset_1, amount_0, amount_1, lp_minted]
+        LiquidityAdded(T::AccountId, T::AssetId, T::AssetId, T::Balance, T::Balance, T::Balance),
+        /// Event emitted when liquidity is removed [who, asset_0, asset_1, amount_0, amount_1, lp_burned]
+        LiquidityRemoved(T::AccountId, T::AssetId, T::AssetId, T::Balance, T::Balance, T::Balance),
+        /// Event emitted when assets are swapped [who, path, amount_in, amount_out]
+        Swapped(T::AccountId, Vec<T::AssetId>, T::Balance, T::Balance),
+    }
+
+    #[pallet::error]
+    pub enum Error<T> {
+        /// Pool assets must differ
+        IdenticalAssets,
+        /// A pool already exists for the pair
+        PoolExists,
+        /// No pool exists for the pair
+        PoolNotFound,
+        /// Amount is zero
+        ZeroAmount,
+        /// Pool reserves cannot cover the trade
+        InsufficientLiquidity,
+        /// Trade would receive less than the minimum
+        InsufficientOutput,
+        /// Trade would pay more than the maximum
+        ExcessiveInput,
+        /// Route has fewer than two or more than `MaxPathLength` assets, or trades through a pool twice
+        InvalidPath,
+        /// Account does not hold enough LP tokens
+        InsufficientLpBalance,
+        /// Transfer of an asset failed
+        TransferFailed,
+        /// Arithmetic overflow
+        Overflow,
+    }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        #[pallet::weight(700_000)]
+        #[transactional]
+        pub fn create_pool(
+            origin: OriginFor<T>,
+            asset_a: T::AssetId,
+            asset_b: T::AssetId,
+            lp_asset_id: T::AssetId,
+        ) -> DispatchResultWithPostInfo {
+            T::CreateOrigin::ensure_origin(origin)?;
+            let (asset_0, asset_1) = Self::sort(asset_a, asset_b)?;
+            ensure!(!Pools::<T>::contains_key(asset_0, asset_1), Error::<T>::PoolExists);
+
+            T::LpTokenFactory::create(lp_asset_id, &Self::account_id(), 1u32.into())?;
+            Pools::<T>::insert(asset_0, asset_1, Pool { lp_asset_id, ..Default::default() });
+            Self::deposit_event(Event::PoolCreated(asset_0, asset_1, lp_asset_id));
+            Ok(().into())
+        }
+
+        // Deposit both assets at the pool ratio for LP tokens
+        //
+        // @param amount_a The most of `asset_a` to deposit
+        // @param amount_b The most of `asset_b` to deposit
+        // @param min_lp The fewest LP tokens accepted
+        #[pallet::weight(700_000)]
+        #[transactional]
+        pub fn add_liquidity(
+            origin: OriginFor<T>,
+            asset_a: T::AssetId,
+            asset_b: T::AssetId,
+            amount_a: T::Balance,
+            amount_b: T::Balance,
+            min_lp: T::Balance,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+            ensure!(!amount_a.is_zero() && !amount_b.is_zero(), Error::<T>::ZeroAmount);
+            let (asset_0, asset_1) = Self::sort(asset_a, asset_b)?;
+            let (amount_0, amount_1) = if asset_a == asset_0 { (amount_a, amount_b) } else { (amount_b, amount_a) };
+            let mut pool = Pools::<T>::get(asset_0, asset_1).ok_or(Error::<T>::PoolNotFound)?;
+
+            let supply = T::Currencies::total_issuance(pool.lp_asset_id);
+            let (amount_0, amount_1, minted) = if supply.is_zero() {
+                // The first deposit sets the price
+                let minted = (Self::u256(amount_0) * Self::u256(amount_1)).integer_sqrt();
+                (amount_0, amount_1, Self::balance(minted)?)
+            } else {
+                let optimal_1 = Self::mul_div(amount_0, pool.reserve_1, pool.reserve_0)?;
+                let (amount_0, amount_1) = if optimal_1 <= amount_1 {
+                    (amount_0, optimal_1)
+                } else {
+                    (Self::mul_div(amount_1, pool.reserve_0, pool.reserve_1)?, amount_1)
+                };
+                let minted = Self::mul_div(amount_0, supply, pool.reserve_0)?
+                    .min(Self::mul_div(amount_1, supply, pool.reserve_1)?);
+                (amount_0, amount_1, minted)
+            };
+            ensure!(!minted.is_zero(), Error::<T>::ZeroAmount);
+            ensure!(minted >= min_lp, Error::<T>::InsufficientOutput);
+
+            let account = Self::account_id();
+            T::Currencies::transfer(asset_0, &who, &account, amount_0, false).map_err(|_| Error::<T>::TransferFailed)?;
+            T::Currencies::transfer(asset_1, &who, &account, amount_1, false).map_err(|_| Error::<T>::TransferFailed)?;
+            T::Currencies::mint_into(pool.lp_asset_id, &who, minted)?;
+
+            pool.reserve_0 = pool.reserve_0.checked_add(&amount_0).ok_or(Error::<T>::Overflow)?;
+            pool.reserve_1 = pool.reserve_1.checked_add(&amount_1).ok_or(Error::<T>::Overflow)?;
+            Pools::<T>::insert(asset_0, asset_1, pool);
+            Self::deposit_event(Event::LiquidityAdded(who, asset_0, asset_1, amount_0, amount_1, minted));
+            Ok(().into())
+        }
+
+        // Burn LP tokens for their share of both reserves
+        //
+        // @param lp_amount The LP tokens to burn
+        // @param min_a The fewest of `asset_a` accepted
+        // @param min_b The fewest of `asset_b` accepted
+        #[pallet::weight(700_000)]
+        #[transactional]
+        pub fn remove_liquidity(
+            origin: OriginFor<T>,
+            asset_a: T::AssetId,
+            asset_b: T::AssetId,
+            lp_amount: T::Balance,
+            min_a: T::Balance,
+            min_b: T::Balance,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+            ensure!(!lp_amount.is_zero(), Error::<T>::ZeroAmount);
+            let (asset_0, asset_1) = Self::sort(asset_a, asset_b)?;
+            let mut pool = Pools::<T>::get(asset_0, asset_1).ok_or(Error::<T>::PoolNotFound)?;
+            ensure!(
+                T::Currencies::balance(pool.lp_asset_id, &who) >= lp_amount,
+                Error::<T>::InsufficientLpBalance
+            );
+
+            let supply = T::Currencies::total_issuance(pool.lp_asset_id);
+            let amount_0 = Self::mul_div(lp_amount, pool.reserve_0, supply)?;
+            let amount_1 = Self::mul_div(lp_amount, pool.reserve_1, supply)?;
+            let (min_0, min_1) = if asset_a == asset_0 { (min_a, min_b) } else { (min_b, min_a) };
+            ensure!(amount_0 >= min_0 && amount_1 >= min_1, Error::<T>::InsufficientOutput);
+
+            T::Currencies::burn_from(pool.lp_asset_id, &who, lp_amount)?;
+            let account = Self::account_id();
+            T::Currencies::transfer(asset_0, &account, &who, amount_0, false).map_err(|_| Error::<T>::TransferFailed)?;
+            T::Currencies::transfer(asset_1, &account, &who, amount_1, false).map_err(|_| Error::<T>::TransferFailed)?;
+
+            pool.reserve_0 -= amount_0;
+            pool.reserve_1 -= amount_1;
+            Pools::<T>::insert(asset_0, asset_1, pool);
+            Self::deposit_event(Event::LiquidityRemoved(who, asset_0, asset_1, amount_0, amount_1, lp_amount));
+            Ok(().into())
+        }
+
+        // Swap an exact amount of the first asset of `path` for at least `min_out` of the last
+        //
+        // @param path The assets to route through, at most `MaxPathLength`
+        #[pallet::weight(700_000u64.saturating_mul(path.len() as Weight))]
+        pub fn swap_exact_in(
+            origin: OriginFor<T>,
+            path: Vec<T::AssetId>,
+            amount_in: T::Balance,
+            min_out: T::Balance,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+            Self::do_swap_exact_in(&who, path, amount_in, min_out)?;
+            Ok(().into())
+        }
+
+        // Swap at most `max_in` of the first asset of `path` for an exact amount of the last
+        //
+        // @param path The assets to route through, at most `MaxPathLength`
+        #[pallet::weight(700_000u64.saturating_mul(path.len() as Weight))]
+        pub fn swap_exact_out(
+            origin: OriginFor<T>,
+            path: Vec<T::AssetId>,
+            amount_out: T::Balance,
+            max_in: T::Balance,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+            Self::do_swap_exact_out(&who, path, amount_out, max_in)?;
+            Ok(().into())
+        }
+    }
+}
+
+impl<T: Config> Pallet<T> {
+    /// The account ID holding the pool reserves.
+    pub fn account_id() -> T::AccountId {
+        T::PalletId::get().into_account()
+    }
+
+    /// Amount of the last asset of `path` received for `amount_in` of the first.
+    pub fn quote_exact_in(path: &[T::AssetId], amount_in: T::Balance) -> Result<T::Balance, Error<T>> {
+        let amounts = Self::amounts_out(path, amount_in)?;
+        Ok(amounts[amounts.len() - 1])
+    }
+
+    /// Amount of the first asset of `path` paid for `amount_out` of the last.
+    pub fn quote_exact_out(path: &[T::AssetId], amount_out: T::Balance) -> Result<T::Balance, Error<T>> {
+        let amounts = Self::amounts_in(path, amount_out)?;
+        Ok(amounts[0])
+    }
+
+    /// Swap `amount_in` of the first asset of `path` held by `who`, returning the amount received.
+    #[transactional]
+    pub fn do_swap_exact_in(
+        who: &T::AccountId,
+        path: Vec<T::AssetId>,
+        amount_in: T::Balance,
+        min_out: T::Balance,
+    ) -> Result<T::Balance, DispatchError> {
+        let amounts = Self::amounts_out(&path, amount_in)?;
+        let amount_out = amounts[amounts.len() - 1];
+        ensure!(amount_out >= min_out, Error::<T>::InsufficientOutput);
+        Self::execute_swap(who, path, &amounts)?;
+        Ok(amount_out)
+    }
+
+    /// Swap the first asset of `path` held by `who` for `amount_out` of the last, returning the amount paid.
+    #[transactional]
+    pub fn do_swap_exact_out(
+        who: &T::AccountId,
+        path: Vec<T::AssetId>,
+        amount_out: T::Balance,
+        max_in: T::Balance,
+    ) -> Result<T::Balance, DispatchError> {
+        let amounts = Self::amounts_in(&path, amount_out)?;
+        let amount_in = amounts[0];
+        ensure!(amount_in <= max_in, Error::<T>::ExcessiveInput);
+        Self::execute_swap(who, path, &amounts)?;
+        Ok(amount_in)
+    }
+
+    /// Move `amounts[i]` of `path[i]` into each pool of the route and pay out the last amount.
+    fn execute_swap(who: &T::AccountId, path: Vec<T::AssetId>, amounts: &[T::Balance]) -> DispatchResult {
+        let account = Self::account_id();
+        let last = path.len() - 1;
+        T::Currencies::transfer(path[0], who, &account, amounts[0], false).map_err(|_| Error::<T>::TransferFailed)?;
+        for (hop, pair) in path.windows(2).enumerate() {
+            let (asset_0, asset_1) = Self::sort(pair[0], pair[1])?;
+            Pools::<T>::try_mutate(asset_0, asset_1, |pool| -> DispatchResult {
+                let pool = pool.as_mut().ok_or(Error::<T>::PoolNotFound)?;
+                let (reserve_in, reserve_out) = pool.reserves(&pair[0], &pair[1]);
+                let reserve_in = reserve_in.checked_add(&amounts[hop]).ok_or(Error::<T>::Overflow)?;
+                pool.set_reserves(&pair[0], &pair[1], reserve_in, reserve_out - amounts[hop + 1]);
+                Ok(())
+            })?;
+        }
+        T::Currencies::transfer(path[last], &account, who, amounts[last], false).map_err(|_| Error::<T>::TransferFailed)?;
+        Self::deposit_event(Event::Swapped(who.clone(), path, amounts[0], amounts[last]));
+        Ok(())
+    }
+
+    /// Amounts at every step of `path` when selling `amount_in` of its first asset.
+    fn amounts_out(path: &[T::AssetId], amount_in: T::Balance) -> Result<Vec<T::Balance>, Error<T>> {
+        Self::ensure_path(path)?;
+        ensure!(!amount_in.is_zero(), Error::<T>::ZeroAmount);
+        let mut amounts = Vec::with_capacity(path.len());
+        amounts.push(amount_in);
+        for pair in path.windows(2) {
+            let (reserve_in, reserve_out) = Self::reserves(pair[0], pair[1])?;
+            let amount_out = Self::amount_out(amounts[amounts.len() - 1], reserve_in, reserve_out)?;
+            amounts.push(amount_out);
+        }
+        Ok(amounts)
+    }
+
+    /// Amounts at every step of `path` when buying `amount_out` of its last asset.
+    fn amounts_in(path: &[T::AssetId], amount_out: T::Balance) -> Result<Vec<T::Balance>, Error<T>> {
+        Self::ensure_path(path)?;
+        ensure!(!amount_out.is_zero(), Error::<T>::ZeroAmount);
+        let mut amounts = vec![amount_out; path.len()];
+        for hop in (0..path.len() - 1).rev() {
+            let (reserve_in, reserve_out) = Self::reserves(path[hop], path[hop + 1])?;
+            amounts[hop] = Self::amount_in(amounts[hop + 1], reserve_in, reserve_out)?;
+        }
+        Ok(amounts)
+    }
+
+    /// Output of selling `amount_in` into a pool, after the swap fee.
+    ///
+    /// `amount_out = amount_in * (1 - fee) * reserve_out / (reserve_in + amount_in * (1 - fee))`
+    pub fn amount_out(
+        amount_in: T::Balance,
+        reserve_in: T::Balance,
+        reserve_out: T::Balance,
+    ) -> Result<T::Balance, Error<T>> {
+        ensure!(!reserve_in.is_zero() && !reserve_out.is_zero(), Error::<T>::InsufficientLiquidity);
+        let in_with_fee = Self::u256(amount_in) * Self::fee_complement();
+        let numerator = in_with_fee * Self::u256(reserve_out);
+        let denominator = Self::u256(reserve_in) * U256::from(Permill::ACCURACY) + in_with_fee;
+        let amount_out = Self::balance(numerator / denominator)?;
+        ensure!(!amount_out.is_zero(), Error::<T>::InsufficientOutput);
+        Ok(amount_out)
+    }
+
+    /// Input needed to buy `amount_out` from a pool, after the swap fee, rounded up.
+    pub fn amount_in(
+        amount_out: T::Balance,
+        reserve_in: T::Balance,
+        reserve_out: T::Balance,
+    ) -> Result<T::Balance, Error<T>> {
+        ensure!(!reserve_in.is_zero() && amount_out < reserve_out, Error::<T>::InsufficientLiquidity);
+        let numerator = Self::u256(reserve_in) * Self::u256(amount_out) * U256::from(Permill::ACCURACY);
+        let denominator = Self::u256(reserve_out - amount_out) * Self::fee_complement();
+        let amount_in = numerator.checked_div(denominator).ok_or(Error::<T>::InsufficientLiquidity)?;
+        Self::balance(amount_in + U256::one())
+    }
+
+    /// Reserves of the pool of a pair, `asset_in` first.
+    fn reserves(asset_in: T::AssetId, asset_out: T::AssetId) -> Result<(T::Balance, T::Balance), Error<T>> {
+        let (asset_0, asset_1) = Self::sort(asset_in, asset_out)?;
+        let pool = Pools::<T>::get(asset_0, asset_1).ok_or(Error::<T>::PoolNotFound)?;
+        Ok(pool.reserves(&asset_in, &asset_out))
+    }
+
+    fn ensure_path(path: &[T::AssetId]) -> Result<(), Error<T>> {
+        ensure!(path.len() >= 2 && path.len() as u32 <= T::MaxPathLength::get(), Error::<T>::InvalidPath);
+        // A pool traded twice would be quoted at the reserves it had before the first hop
+        let pairs: Vec<(T::AssetId, T::AssetId)> = path
+            .windows(2)
+            .map(|pair| if pair[0] < pair[1] { (pair[0], pair[1]) } else { (pair[1], pair[0]) })
+            .collect();
+        for (i, pair) in pairs.iter().enumerate() {
+            ensure!(!pairs[..i].contains(pair), Error::<T>::InvalidPath);
+        }
+        Ok(())
+    }
+
+    fn sort(asset_a: T::AssetId, asset_b: T::AssetId) -> Result<(T::AssetId, T::AssetId), Error<T>> {
+        ensure!(asset_a != asset_b, Error::<T>::IdenticalAssets);
+        Ok(if asset_a < asset_b { (asset_a, asset_b) } else { (asset_b, asset_a) })
+    }
+
+    /// `amount * numerator / denominator`, rounded down.
+    fn mul_div(amount: T::Balance, numerator: T::Balance, denominator: T::Balance) -> Result<T::Balance, Error<T>> {
+        ensure!(!denominator.is_zero(), Error::<T>::InsufficientLiquidity);
+        Self::balance(Self::u256(amount) * Self::u256(numerator) / Self::u256(denominator))
+    }
+
+    fn fee_complement() -> U256 {
+        U256::from((Permill::one() - T::SwapFee::get()).deconstruct())
+    }
+
+    fn u256(amount: T::Balance) -> U256 {
+        U256::from(amount.saturated_into::<u128>())
+    }
+
+    fn balance(amount: U256) -> Result<T::Balance, Error<T>> {
+        ensure!(amount <= U256::from(u128::max_value()), Error::<T>::Overflow);
+        amount.low_u128().try_into().map_err(|_| Error::<T>::Overflow)
+    }
+}
@@ -0,0 +1,151 @@
+use crate as pallet_amm;
+use sp_core::H256;
+use frame_support::{dispatch::DispatchResult, parameter_types, PalletId};
+use sp_runtime::{
+	traits::{AccountIdConversion, BlakeTwo256, IdentityLookup}, testing::Header, Permill,
+};
+use frame_system as system;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+pub const ALICE: u64 = 1;
+pub const BOB: u64 = 2;
+
+pub const DOT: u32 = 0;
+pub const KSM: u32 = 1;
+pub const USDT: u32 = 2;
+/// LP token of the `DOT`/`KSM` pool.
+pub const DOT_KSM: u32 = 10;
+/// LP token of the `KSM`/`USDT` pool.
+pub const KSM_USDT: u32 = 11;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Module, Call, Config, Storage, Event<T>},
+		Balances: pallet_balances::{Module, Call, Storage, Config<T>, Event<T>},
+		Assets: pallet_assets::{Module, Call, Storage, Event<T>},
+		Amm: pallet_amm::{Module, Call, Storage, Event<T>},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const SS58Prefix: u8 = 42;
+}
+
+impl system::Config for Test {
+	type BaseCallFilter = ();
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = pallet_balances::AccountData<u64>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = SS58Prefix;
+}
+
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 1;
+	pub const MaxLocks: u32 = 50;
+}
+
+impl pallet_balances::Config for Test {
+	type MaxLocks = MaxLocks;
+	type Balance = u64;
+	type Event = Event;
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const AssetDeposit: u64 = 1;
+	pub const StringLimit: u32 = 50;
+	pub const MetadataDepositBase: u64 = 1;
+	pub const MetadataDepositPerByte: u64 = 1;
+}
+
+impl pallet_assets::Config for Test {
+	type Event = Event;
+	type Balance = u64;
+	type AssetId = u32;
+	type Currency = Balances;
+	type ForceOrigin = frame_system::EnsureRoot<u64>;
+	type MetadataDepositBase = MetadataDepositBase;
+	type MetadataDepositPerByte = MetadataDepositPerByte;
+	type StringLimit = StringLimit;
+	type AssetDepositBase = AssetDeposit;
+	type AssetDepositPerZombie = AssetDeposit;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const AmmPalletId: PalletId = PalletId(*b"rp/amm00");
+	pub static SwapFee: Permill = Permill::from_perthousand(3);
+	pub const MaxPathLength: u32 = 3;
+}
+
+/// Creates LP tokens in `pallet_assets`.
+pub struct AssetsLpTokenFactory;
+
+impl pallet_amm::LpTokenFactory<u64, u32, u64> for AssetsLpTokenFactory {
+	fn create(id: u32, owner: &u64, min_balance: u64) -> DispatchResult {
+		Assets::force_create(Origin::root(), id, *owner, 10, min_balance)
+	}
+}
+
+impl pallet_amm::Config for Test {
+	type Event = Event;
+	type Balance = u64;
+	type AssetId = u32;
+	type PalletId = AmmPalletId;
+	type Currencies = Assets;
+	type CreateOrigin = frame_system::EnsureRoot<u64>;
+	type LpTokenFactory = AssetsLpTokenFactory;
+	type SwapFee = SwapFee;
+	type MaxPathLength = MaxPathLength;
+}
+
+pub fn amm_account() -> u64 {
+	AmmPalletId::get().into_account()
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(ALICE, 1_000), (BOB, 1_000), (amm_account(), 1_000)],
+	}.assimilate_storage(&mut t).unwrap();
+
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| {
+		System::set_block_number(1);
+		for asset in [DOT, KSM, USDT].iter() {
+			Assets::force_create(Origin::root(), *asset, ALICE, 10, 1).unwrap();
+			Assets::mint(Origin::signed(ALICE), *asset, ALICE, 10_000).unwrap();
+			Assets::mint(Origin::signed(ALICE), *asset, BOB, 1_000).unwrap();
+		}
+	});
+	ext
+}
@@ -0,0 +1,109 @@
+use crate::{Error, Pool, mock::*};
+use frame_support::{assert_ok, assert_noop, traits::Hooks};
+use sp_runtime::Permill;
+
+/// Creates the `DOT`/`KSM` and `KSM`/`USDT` pools with 1000 of every asset.
+fn create_pools() {
+	assert_ok!(Amm::create_pool(Origin::root(), DOT, KSM, DOT_KSM));
+	assert_ok!(Amm::create_pool(Origin::root(), USDT, KSM, KSM_USDT));
+	assert_ok!(Amm::add_liquidity(Origin::signed(ALICE), DOT, KSM, 1_000, 1_000, 0));
+	assert_ok!(Amm::add_liquidity(Origin::signed(ALICE), KSM, USDT, 1_000, 1_000, 0));
+}
+
+#[test]
+fn liquidity_is_added_and_removed_at_the_pool_ratio() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(Amm::create_pool(Origin::root(), DOT, DOT, DOT_KSM), Error::<Test>::IdenticalAssets);
+		assert_ok!(Amm::create_pool(Origin::root(), KSM, DOT, DOT_KSM));
+		assert_noop!(Amm::create_pool(Origin::root(), DOT, KSM, KSM_USDT), Error::<Test>::PoolExists);
+
+		// The first deposit sets the price and mints the geometric mean
+		assert_ok!(Amm::add_liquidity(Origin::signed(ALICE), DOT, KSM, 100, 400, 0));
+		assert_eq!(Assets::balance(DOT_KSM, ALICE), 200);
+
+		// Later deposits are capped at the pool ratio
+		assert_noop!(
+			Amm::add_liquidity(Origin::signed(BOB), KSM, DOT, 300, 50, 101),
+			Error::<Test>::InsufficientOutput
+		);
+		assert_ok!(Amm::add_liquidity(Origin::signed(BOB), KSM, DOT, 300, 50, 100));
+		assert_eq!(Assets::balance(KSM, BOB), 800);
+		assert_eq!(Amm::pool(DOT, KSM), Some(Pool { lp_asset_id: DOT_KSM, reserve_0: 150, reserve_1: 600 }));
+
+		assert_noop!(
+			Amm::remove_liquidity(Origin::signed(BOB), DOT, KSM, 100, 51, 0),
+			Error::<Test>::InsufficientOutput
+		);
+		assert_ok!(Amm::remove_liquidity(Origin::signed(BOB), DOT, KSM, 100, 50, 200));
+		assert_eq!(Assets::balance(DOT, BOB), 1_000);
+		assert_eq!(Assets::balance(KSM, BOB), 1_000);
+		assert_eq!(Assets::balance(DOT_KSM, BOB), 0);
+	});
+}
+
+#[test]
+fn swaps_respect_slippage_limits() {
+	new_test_ext().execute_with(|| {
+		create_pools();
+
+		// 100 DOT less the 0.3% fee buys 90 of the 1000 KSM
+		assert_eq!(Amm::quote_exact_in(&[DOT, KSM], 100), Ok(90));
+		assert_noop!(
+			Amm::swap_exact_in(Origin::signed(BOB), vec![DOT, KSM], 100, 91),
+			Error::<Test>::InsufficientOutput
+		);
+		assert_ok!(Amm::swap_exact_in(Origin::signed(BOB), vec![DOT, KSM], 100, 90));
+		assert_eq!(Assets::balance(KSM, BOB), 1_090);
+		assert_eq!(Amm::pool(DOT, KSM), Some(Pool { lp_asset_id: DOT_KSM, reserve_0: 1_100, reserve_1: 910 }));
+
+		// Buying back 50 DOT costs 44 KSM, rounded up
+		assert_noop!(
+			Amm::swap_exact_out(Origin::signed(BOB), vec![KSM, DOT], 50, 43),
+			Error::<Test>::ExcessiveInput
+		);
+		assert_ok!(Amm::swap_exact_out(Origin::signed(BOB), vec![KSM, DOT], 50, 44));
+		assert_eq!(Assets::balance(DOT, BOB), 950);
+		assert_eq!(Assets::balance(KSM, BOB), 1_046);
+
+		assert_noop!(
+			Amm::swap_exact_out(Origin::signed(BOB), vec![KSM, DOT], 1_050, 10_000),
+			Error::<Test>::InsufficientLiquidity
+		);
+	});
+}
+
+#[test]
+fn multi_hop_routes_swap_through_every_pool() {
+	new_test_ext().execute_with(|| {
+		create_pools();
+		assert_noop!(
+			Amm::swap_exact_in(Origin::signed(BOB), vec![DOT, KSM, USDT, DOT], 100, 0),
+			Error::<Test>::InvalidPath
+		);
+		assert_noop!(
+			Amm::swap_exact_in(Origin::signed(BOB), vec![DOT, KSM, DOT], 100, 0),
+			Error::<Test>::InvalidPath
+		);
+		assert_noop!(
+			Amm::swap_exact_out(Origin::signed(BOB), vec![KSM, DOT, KSM], 100, 1_000),
+			Error::<Test>::InvalidPath
+		);
+		assert_noop!(Amm::swap_exact_in(Origin::signed(BOB), vec![DOT, USDT], 100, 0), Error::<Test>::PoolNotFound);
+
+		assert_ok!(Amm::swap_exact_in(Origin::signed(BOB), vec![DOT, KSM, USDT], 100, 82));
+		assert_eq!(Assets::balance(DOT, BOB), 900);
+		assert_eq!(Assets::balance(KSM, BOB), 1_000);
+		assert_eq!(Assets::balance(USDT, BOB), 1_082);
+		assert_eq!(Amm::pool(KSM, USDT), Some(Pool { lp_asset_id: KSM_USDT, reserve_0: 1_090, reserve_1: 918 }));
+		assert_eq!(Assets::balance(KSM, amm_account()), 2_000);
+	});
+}
+
+#[test]
+fn a_swap_fee_of_the_whole_input_is_rejected() {
+	new_test_ext().execute_with(|| {
+		SwapFee::set(Permill::one());
+		assert_eq!(Amm::amount_in(10, 1_000, 1_000), Err(Error::<Test>::InsufficientLiquidity));
+		assert!(std::panic::catch_unwind(|| <Amm as Hooks<u64>>::integrity_test()).is_err());
+	});
+}
@@ -0,0 +1,94 @@
+use super::*;
+use codec::{Encode, FullCodec};
+use frame_support::storage::unhashed;
+use sp_runtime::{
+    traits::{AtLeast32BitUnsigned, CheckedAdd, MaybeSerializeDeserialize},
+    DispatchError,
+};
+use sp_std::{fmt::Debug, marker::PhantomData};
+
+const PREFIX: &[u8] = b"InMemoryCurrencies";
+
+/// Currencies for tests, kept in unhashed storage so every test externalities starts empty and
+/// failed transactions are rolled back.
+///
+/// Any asset exists, with a minimum balance of zero and the decimals set by `set_decimals`.
+pub struct InMemoryCurrencies<AssetId, Balance>(PhantomData<(AssetId, Balance)>);
+
+impl<AssetId: Encode, Balance> InMemoryCurrencies<AssetId, Balance> {
+    /// Set the decimals of `asset_id`.
+    pub fn set_decimals(asset_id: AssetId, decimals: u8) {
+        unhashed::put(&(PREFIX, b"decimals", asset_id).encode(), &decimals);
+    }
+
+    fn issuance_key(asset_id: &AssetId) -> Vec<u8> {
+        (PREFIX, b"issuance", asset_id).encode()
+    }
+
+    fn balance_key<AccountId: Encode>(asset_id: &AssetId, who: &AccountId) -> Vec<u8> {
+        (PREFIX, b"balance", asset_id, who).encode()
+    }
+}
+
+impl<AccountId, AssetId, Balance> MultiCurrency<AccountId> for InMemoryCurrencies<AssetId, Balance>
+where
+    AccountId: Encode + Eq,
+    AssetId: FullCodec + Eq + PartialEq + Copy + MaybeSerializeDeserialize + Debug,
+    Balance: AtLeast32BitUnsigned + FullCodec + Copy + Default + MaybeSerializeDeserialize + Debug,
+{
+    type AssetId = AssetId;
+    type Balance = Balance;
+
+    fn total_issuance(asset_id: AssetId) -> Balance {
+        unhashed::get_or_default(&Self::issuance_key(&asset_id))
+    }
+
+    fn minimum_balance(_: AssetId) -> Balance {
+        Zero::zero()
+    }
+
+    fn decimals(asset_id: AssetId) -> u8 {
+        unhashed::get_or_default(&(PREFIX, b"decimals", asset_id).encode())
+    }
+
+    fn total_balance(asset_id: AssetId, who: &AccountId) -> Balance {
+        Self::free_balance(asset_id, who)
+    }
+
+    fn free_balance(asset_id: AssetId, who: &AccountId) -> Balance {
+        unhashed::get_or_default(&Self::balance_key(&asset_id, who))
+    }
+
+    fn ensure_can_withdraw(asset_id: AssetId, who: &AccountId, amount: Balance) -> DispatchResult {
+        if Self::free_balance(asset_id, who) < amount {
+            return Err(DispatchError::Other("balance too low"));
+        }
+        Ok(())
+    }
+
+    fn transfer(asset_id: AssetId, from: &AccountId, to: &AccountId, amount: Balance) -> DispatchResult {
+        Self::ensure_can_withdraw(asset_id, from, amount)?;
+        if from == to {
+            return Ok(());
+        }
+        unhashed::put(&Self::balance_key(&asset_id, from), &(Self::free_balance(asset_id, from) - amount));
+        unhashed::put(&Self::balance_key(&asset_id, to), &Self::free_balance(asset_id, to).saturating_add(amount));
+        Ok(())
+    }
+
+    fn mint(asset_id: AssetId, who: &AccountId, amount: Balance) -> DispatchResult {
+        let issuance = Self::total_issuance(asset_id)
+            .checked_add(&amount)
+            .ok_or(DispatchError::Other("issuance overflow"))?;
+        unhashed::put(&Self::issuance_key(&asset_id), &issuance);
+        unhashed::put(&Self::balance_key(&asset_id, who), &(Self::free_balance(asset_id, who) + amount));
+        Ok(())
+    }
+
+    fn burn(asset_id: AssetId, who: &AccountId, amount: Balance) -> DispatchResult {
+        Self::ensure_can_withdraw(asset_id, who, amount)?;
+        unhashed::put(&Self::balance_key(&asset_id, who), &(Self::free_balance(asset_id, who) - amount));
+        unhashed::put(&Self::issuance_key(&asset_id), &Self::total_issuance(asset_id).saturating_sub(amount));
+        Ok(())
+    }
+}
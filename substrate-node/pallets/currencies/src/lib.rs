@@ -0,0 +1,240 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Multi-currency abstraction shared by the vault, the liquidator and the liquidator adapter.
+//!
+//! The [`MultiCurrency`] traits cover transfers, minting and burning, total issuance and decimals,
+//! reserves and locks. The pallet implements them over any `fungibles` backend such as
+//! `pallet_assets`: reserved balances are moved to the pallet account and locks are enforced by
+//! `ensure_can_withdraw` on every transfer, burn and reserve made through it. The backend keeps no
+//! record of locks, so pallets locking balances must only move them through this pallet.
+//! [`InMemoryCurrencies`] implements [`MultiCurrency`] without a backend for testing pallets
+//! generic over it.
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+mod traits;
+pub use traits::{Decimals, MultiCurrency, MultiLockableCurrency, MultiReservableCurrency};
+
+#[cfg(feature = "std")]
+mod in_memory;
+#[cfg(feature = "std")]
+pub use in_memory::InMemoryCurrencies;
+
+use sp_std::prelude::*;
+use frame_support::{
+    ensure,
+    traits::{
+        tokens::fungibles::{Inspect, Mutate, Transfer},
+        Get, LockIdentifier,
+    },
+};
+use sp_runtime::{
+    traits::{AccountIdConversion, CheckedSub, Saturating, Zero},
+    DispatchResult,
+};
+
+#[frame_support::pallet]
+pub mod pallet {
+    use super::*;
+    use frame_support::{pallet_prelude::*, PalletId};
+    use frame_system::pallet_prelude::*;
+    use sp_runtime::traits::{AtLeast32BitUnsigned, MaybeSerializeDeserialize, Member};
+
+    #[pallet::config]
+    pub trait Config: frame_system::Config {
+        type AssetId: Member + Parameter + Copy + MaybeSerializeDeserialize;
+
+        type Balance: Member + Parameter + AtLeast32BitUnsigned + Default + Copy + MaybeSerializeDeserialize;
+
+        /// Backend holding the balances.
+        type Assets: Inspect<Self::AccountId, AssetId = Self::AssetId, Balance = Self::Balance>
+            + Mutate<Self::AccountId>
+            + Transfer<Self::AccountId>;
+
+        /// Decimals metadata of the assets.
+        type Decimals: Decimals<Self::AssetId>;
+
+        /// The pallet id, used for deriving the account holding reserved balances.
+        #[pallet::constant]
+        type PalletId: Get<PalletId>;
+
+        /// Maximum number of locks on the balance of an account.
+        #[pallet::constant]
+        type MaxLocks: Get<u32>;
+    }
+
+    #[pallet::pallet]
+    #[pallet::generate_store(pub(super) trait Store)]
+    pub struct Pallet<T>(_);
+
+    /// Reserved balance of an account
+    #[pallet::storage]
+    #[pallet::getter(fn reserves)]
+    pub type Reserves<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AssetId,
+        Blake2_128Concat,
+        T::AccountId,
+        T::Balance,
+        ValueQuery,
+    >;
+
+    /// Locks on the balance of an account
+    #[pallet::storage]
+    #[pallet::getter(fn locks)]
+    pub type Locks<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AssetId,
+        Blake2_128Concat,
+        T::AccountId,
+        Vec<(LockIdentifier, T::Balance)>,
+        ValueQuery,
+    >;
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+
+    #[pallet::error]
+    pub enum Error<T> {
+        /// Balance too low to withdraw the amount
+        BalanceTooLow,
+        /// The amount would leave less than the locked balance
+        LiquidityRestrictions,
+        /// Too many locks on the balance
+        TooManyLocks,
+    }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {}
+
+    impl<T: Config> Pallet<T> {
+        /// Account holding the reserved balances.
+        pub fn account_id() -> T::AccountId {
+            T::PalletId::get().into_account()
+        }
+
+        /// Free balance of `who` that the largest of its locks keeps in the account.
+        pub fn frozen_balance(asset_id: T::AssetId, who: &T::AccountId) -> T::Balance {
+            Locks::<T>::get(asset_id, who)
+                .into_iter()
+                .map(|(_, amount)| amount)
+                .max()
+                .unwrap_or_else(Zero::zero)
+        }
+    }
+}
+
+impl<T: Config> MultiCurrency<T::AccountId> for Pallet<T> {
+    type AssetId = T::AssetId;
+    type Balance = T::Balance;
+
+    fn total_issuance(asset_id: T::AssetId) -> T::Balance {
+        T::Assets::total_issuance(asset_id)
+    }
+
+    fn minimum_balance(asset_id: T::AssetId) -> T::Balance {
+        T::Assets::minimum_balance(asset_id)
+    }
+
+    fn decimals(asset_id: T::AssetId) -> u8 {
+        T::Decimals::decimals(asset_id)
+    }
+
+    fn total_balance(asset_id: T::AssetId, who: &T::AccountId) -> T::Balance {
+        Self::free_balance(asset_id, who).saturating_add(Reserves::<T>::get(asset_id, who))
+    }
+
+    fn free_balance(asset_id: T::AssetId, who: &T::AccountId) -> T::Balance {
+        T::Assets::balance(asset_id, who)
+    }
+
+    fn ensure_can_withdraw(asset_id: T::AssetId, who: &T::AccountId, amount: T::Balance) -> DispatchResult {
+        let remaining = Self::free_balance(asset_id, who).checked_sub(&amount).ok_or(Error::<T>::BalanceTooLow)?;
+        ensure!(remaining >= Self::frozen_balance(asset_id, who), Error::<T>::LiquidityRestrictions);
+        Ok(())
+    }
+
+    fn transfer(asset_id: T::AssetId, from: &T::AccountId, to: &T::AccountId, amount: T::Balance) -> DispatchResult {
+        if amount.is_zero() || from == to {
+            return Ok(());
+        }
+        Self::ensure_can_withdraw(asset_id, from, amount)?;
+        T::Assets::transfer(asset_id, from, to, amount, false)?;
+        Ok(())
+    }
+
+    fn mint(asset_id: T::AssetId, who: &T::AccountId, amount: T::Balance) -> DispatchResult {
+        if amount.is_zero() {
+            return Ok(());
+        }
+        T::Assets::mint_into(asset_id, who, amount)
+    }
+
+    fn burn(asset_id: T::AssetId, who: &T::AccountId, amount: T::Balance) -> DispatchResult {
+        if amount.is_zero() {
+            return Ok(());
+        }
+        Self::ensure_can_withdraw(asset_id, who, amount)?;
+        T::Assets::burn_from(asset_id, who, amount)?;
+        Ok(())
+    }
+}
+
+impl<T: Config> MultiReservableCurrency<T::AccountId> for Pallet<T> {
+    fn reserved_balance(asset_id: T::AssetId, who: &T::AccountId) -> T::Balance {
+        Reserves::<T>::get(asset_id, who)
+    }
+
+    fn reserve(asset_id: T::AssetId, who: &T::AccountId, amount: T::Balance) -> DispatchResult {
+        if amount.is_zero() {
+            return Ok(());
+        }
+        Self::ensure_can_withdraw(asset_id, who, amount)?;
+        T::Assets::transfer(asset_id, who, &Self::account_id(), amount, false)?;
+        Reserves::<T>::mutate(asset_id, who, |reserved| *reserved = reserved.saturating_add(amount));
+        Ok(())
+    }
+
+    fn unreserve(asset_id: T::AssetId, who: &T::AccountId, amount: T::Balance) -> T::Balance {
+        let reserved = Reserves::<T>::get(asset_id, who);
+        let actual = amount.min(reserved);
+        if actual.is_zero() || T::Assets::transfer(asset_id, &Self::account_id(), who, actual, false).is_err() {
+            return amount;
+        }
+        Reserves::<T>::insert(asset_id, who, reserved.saturating_sub(actual));
+        amount.saturating_sub(actual)
+    }
+}
+
+impl<T: Config> MultiLockableCurrency<T::AccountId> for Pallet<T> {
+    fn set_lock(id: LockIdentifier, asset_id: T::AssetId, who: &T::AccountId, amount: T::Balance) -> DispatchResult {
+        Locks::<T>::try_mutate(asset_id, who, |locks| -> DispatchResult {
+            match locks.iter_mut().find(|(lock_id, _)| *lock_id == id) {
+                Some(lock) => lock.1 = amount,
+                None => {
+                    ensure!((locks.len() as u32) < T::MaxLocks::get(), Error::<T>::TooManyLocks);
+                    locks.push((id, amount));
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn remove_lock(id: LockIdentifier, asset_id: T::AssetId, who: &T::AccountId) {
+        let mut locks = Locks::<T>::get(asset_id, who);
+        locks.retain(|(lock_id, _)| *lock_id != id);
+        if locks.is_empty() {
+            Locks::<T>::remove(asset_id, who);
+        } else {
+            Locks::<T>::insert(asset_id, who, locks);
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use crate::{Error, InMemoryCurrencies, MultiCurrency, MultiLockableCurrency, MultiReservableCurrency, mock::*};
+use frame_support::{assert_ok, assert_noop};
+use sp_runtime::DispatchError;
+
+const LOCK: [u8; 8] = *b"testlock";
+const OTHER_LOCK: [u8; 8] = *b"otherlck";
+
+#[test]
+fn mint_and_burn_change_total_issuance() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Currencies::mint(DOT, &BOB, 500));
+		assert_eq!(Currencies::free_balance(DOT, &BOB), 500);
+		assert_eq!(Currencies::total_issuance(DOT), 1_500);
+
+		assert_ok!(Currencies::burn(DOT, &BOB, 200));
+		assert_eq!(Currencies::free_balance(DOT, &BOB), 300);
+		assert_eq!(Currencies::total_issuance(DOT), 1_300);
+		assert_noop!(Currencies::burn(DOT, &BOB, 301), Error::<Test>::BalanceTooLow);
+
+		assert_eq!(Currencies::decimals(DOT), 10);
+		assert_eq!(Currencies::minimum_balance(DOT), 1);
+	});
+}
+
+#[test]
+fn locks_restrict_withdrawals() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Currencies::set_lock(LOCK, DOT, &ALICE, 600));
+		assert_ok!(Currencies::set_lock(OTHER_LOCK, DOT, &ALICE, 300));
+		assert_eq!(Currencies::frozen_balance(DOT, &ALICE), 600);
+		assert_noop!(Currencies::transfer(DOT, &ALICE, &BOB, 401), Error::<Test>::LiquidityRestrictions);
+		assert_noop!(Currencies::burn(DOT, &ALICE, 401), Error::<Test>::LiquidityRestrictions);
+		assert_ok!(Currencies::transfer(DOT, &ALICE, &BOB, 400));
+		assert_eq!(Currencies::free_balance(DOT, &BOB), 400);
+		assert_noop!(Currencies::reserve(DOT, &ALICE, 1), Error::<Test>::LiquidityRestrictions);
+
+		// Setting a lock again replaces it, a third one is too many
+		assert_ok!(Currencies::set_lock(LOCK, DOT, &ALICE, 100));
+		assert_eq!(Currencies::frozen_balance(DOT, &ALICE), 300);
+		assert_noop!(Currencies::set_lock(*b"thrdlock", DOT, &ALICE, 100), Error::<Test>::TooManyLocks);
+
+		Currencies::remove_lock(OTHER_LOCK, DOT, &ALICE);
+		Currencies::remove_lock(LOCK, DOT, &ALICE);
+		assert_eq!(Currencies::frozen_balance(DOT, &ALICE), 0);
+		assert_ok!(Currencies::transfer(DOT, &ALICE, &BOB, 600));
+	});
+}
+
+#[test]
+fn reserves_leave_the_free_balance() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Currencies::reserve(DOT, &ALICE, 300));
+		assert_eq!(Currencies::free_balance(DOT, &ALICE), 700);
+		assert_eq!(Currencies::reserved_balance(DOT, &ALICE), 300);
+		assert_eq!(Currencies::total_balance(DOT, &ALICE), 1_000);
+		assert_eq!(Currencies::free_balance(DOT, &currencies_account()), 300);
+		assert_noop!(Currencies::reserve(DOT, &ALICE, 701), Error::<Test>::BalanceTooLow);
+
+		// Only what is reserved is returned
+		assert_eq!(Currencies::unreserve(DOT, &ALICE, 500), 200);
+		assert_eq!(Currencies::free_balance(DOT, &ALICE), 1_000);
+		assert_eq!(Currencies::reserved_balance(DOT, &ALICE), 0);
+		assert_eq!(Currencies::unreserve(DOT, &ALICE, 100), 100);
+	});
+}
+
+#[test]
+fn in_memory_currencies_move_balances() {
+	new_test_ext().execute_with(|| {
+		type Memory = InMemoryCurrencies<u32, u64>;
+		assert_ok!(<Memory as MultiCurrency<u64>>::mint(DOT, &ALICE, 100));
+		assert_ok!(<Memory as MultiCurrency<u64>>::transfer(DOT, &ALICE, &BOB, 40));
+		assert_ok!(<Memory as MultiCurrency<u64>>::burn(DOT, &BOB, 10));
+		assert_eq!(<Memory as MultiCurrency<u64>>::free_balance(DOT, &ALICE), 60);
+		assert_eq!(<Memory as MultiCurrency<u64>>::free_balance(DOT, &BOB), 30);
+		assert_eq!(<Memory as MultiCurrency<u64>>::total_issuance(DOT), 90);
+		assert_noop!(
+			<Memory as MultiCurrency<u64>>::transfer(DOT, &BOB, &ALICE, 31),
+			DispatchError::Other("balance too low")
+		);
+
+		Memory::set_decimals(DOT, 12);
+		assert_eq!(<Memory as MultiCurrency<u64>>::decimals(DOT), 12);
+	});
+}
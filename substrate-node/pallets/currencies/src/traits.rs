@@ -0,0 +1,77 @@
+use codec::FullCodec;
+use frame_support::traits::LockIdentifier;
+use sp_runtime::{
+    traits::{AtLeast32BitUnsigned, MaybeSerializeDeserialize},
+    DispatchResult,
+};
+use sp_std::fmt::Debug;
+
+/// Balances of several currencies, each identified by an asset id.
+pub trait MultiCurrency<AccountId> {
+    type AssetId: FullCodec + Eq + PartialEq + Copy + MaybeSerializeDeserialize + Debug;
+
+    type Balance: AtLeast32BitUnsigned + FullCodec + Copy + Default + MaybeSerializeDeserialize + Debug;
+
+    /// Amount of `asset_id` in existence.
+    fn total_issuance(asset_id: Self::AssetId) -> Self::Balance;
+
+    /// Smallest balance of `asset_id` an account may hold.
+    fn minimum_balance(asset_id: Self::AssetId) -> Self::Balance;
+
+    /// Decimals of one whole unit of `asset_id`.
+    fn decimals(asset_id: Self::AssetId) -> u8;
+
+    /// Free and reserved balance of `who`.
+    fn total_balance(asset_id: Self::AssetId, who: &AccountId) -> Self::Balance;
+
+    /// Balance of `who` that is not reserved, part of which may be locked.
+    fn free_balance(asset_id: Self::AssetId, who: &AccountId) -> Self::Balance;
+
+    /// Fail unless `amount` of the free balance of `who` may leave the account.
+    fn ensure_can_withdraw(asset_id: Self::AssetId, who: &AccountId, amount: Self::Balance) -> DispatchResult;
+
+    /// Move `amount` of the free balance of `from` to `to`.
+    fn transfer(asset_id: Self::AssetId, from: &AccountId, to: &AccountId, amount: Self::Balance) -> DispatchResult;
+
+    /// Create `amount` into the free balance of `who`, increasing the total issuance.
+    fn mint(asset_id: Self::AssetId, who: &AccountId, amount: Self::Balance) -> DispatchResult;
+
+    /// Destroy `amount` of the free balance of `who`, decreasing the total issuance.
+    fn burn(asset_id: Self::AssetId, who: &AccountId, amount: Self::Balance) -> DispatchResult;
+}
+
+/// Currencies whose balances can be set aside, out of reach of transfers.
+pub trait MultiReservableCurrency<AccountId>: MultiCurrency<AccountId> {
+    /// Balance of `who` set aside.
+    fn reserved_balance(asset_id: Self::AssetId, who: &AccountId) -> Self::Balance;
+
+    /// Move `amount` of the free balance of `who` to its reserved balance.
+    fn reserve(asset_id: Self::AssetId, who: &AccountId, amount: Self::Balance) -> DispatchResult;
+
+    /// Move up to `amount` of the reserved balance of `who` back to its free balance, returning
+    /// the part that could not be unreserved.
+    fn unreserve(asset_id: Self::AssetId, who: &AccountId, amount: Self::Balance) -> Self::Balance;
+}
+
+/// Currencies whose free balances can be locked, staying in the account but unable to leave it.
+///
+/// Locks overlap, the locked balance of an account is the largest of its locks.
+pub trait MultiLockableCurrency<AccountId>: MultiCurrency<AccountId> {
+    /// Lock `amount` of the balance of `who` under `id`, replacing an earlier lock of that id.
+    fn set_lock(id: LockIdentifier, asset_id: Self::AssetId, who: &AccountId, amount: Self::Balance) -> DispatchResult;
+
+    /// Remove the lock `id` from the balance of `who`.
+    fn remove_lock(id: LockIdentifier, asset_id: Self::AssetId, who: &AccountId);
+}
+
+/// Decimals metadata of assets.
+pub trait Decimals<AssetId> {
+    /// Decimals of one whole unit of `asset_id`.
+    fn decimals(asset_id: AssetId) -> u8;
+}
+
+impl<AssetId> Decimals<AssetId> for () {
+    fn decimals(_: AssetId) -> u8 {
+        0
+    }
+}
@@ -0,0 +1,89 @@
+//! RPC interface for the liquidator pallet.
+
+use std::sync::Arc;
+
+use codec::Codec;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+pub use pallet_liquidator_runtime_api::LiquidatorApi as LiquidatorRuntimeApi;
+
+#[rpc]
+pub trait LiquidatorApi<BlockHash, AccountId, LiquidationRecord> {
+    #[rpc(name = "liquidator_targetHistory")]
+    fn target_history(
+        &self,
+        who: AccountId,
+        offset: u32,
+        limit: u32,
+        at: Option<BlockHash>,
+    ) -> Result<Vec<LiquidationRecord>>;
+
+    #[rpc(name = "liquidator_finderHistory")]
+    fn finder_history(
+        &self,
+        who: AccountId,
+        offset: u32,
+        limit: u32,
+        at: Option<BlockHash>,
+    ) -> Result<Vec<LiquidationRecord>>;
+}
+
+/// Error code returned when the runtime API call fails.
+const RUNTIME_ERROR: i64 = 1;
+
+/// Implements the liquidator RPC methods by calling into the runtime.
+pub struct Liquidator<C, B> {
+    client: Arc<C>,
+    _marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> Liquidator<C, B> {
+    /// Create new `Liquidator` with the given reference to the client.
+    pub fn new(client: Arc<C>) -> Self {
+        Self { client, _marker: Default::default() }
+    }
+}
+
+fn runtime_error(e: impl std::fmt::Debug) -> RpcError {
+    RpcError {
+        code: ErrorCode::ServerError(RUNTIME_ERROR),
+        message: "Unable to query the liquidation history.".into(),
+        data: Some(format!("{:?}", e).into()),
+    }
+}
+
+impl<C, Block, AccountId, LiquidationRecord> LiquidatorApi<<Block as BlockT>::Hash, AccountId, LiquidationRecord>
+    for Liquidator<C, Block>
+where
+    Block: BlockT,
+    C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+    C::Api: LiquidatorRuntimeApi<Block, AccountId, LiquidationRecord>,
+    AccountId: Codec,
+    LiquidationRecord: Codec,
+{
+    fn target_history(
+        &self,
+        who: AccountId,
+        offset: u32,
+        limit: u32,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Vec<LiquidationRecord>> {
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        self.client.runtime_api().target_history(&at, who, offset, limit).map_err(runtime_error)
+    }
+
+    fn finder_history(
+        &self,
+        who: AccountId,
+        offset: u32,
+        limit: u32,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Vec<LiquidationRecord>> {
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        self.client.runtime_api().finder_history(&at, who, offset, limit).map_err(runtime_error)
+    }
+}
@@ -0,0 +1,17 @@
+//! Runtime API definition for the liquidator pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+use codec::Codec;
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+    pub trait LiquidatorApi<AccountId, LiquidationRecord> where
+        AccountId: Codec,
+        LiquidationRecord: Codec,
+    {
+        /// Liquidations of the positions of `who`, newest first.
+        fn target_history(who: AccountId, offset: u32, limit: u32) -> Vec<LiquidationRecord>;
+        /// Liquidations submitted by `who`, newest first.
+        fn finder_history(who: AccountId, offset: u32, limit: u32) -> Vec<LiquidationRecord>;
+    }
+}
@@ -0,0 +1,45 @@
+use codec::{Decode, Encode};
+use sp_runtime::{
+    traits::{AtLeast32BitUnsigned, Saturating},
+    FixedPointNumber, FixedU128, RuntimeDebug, SaturatedConversion,
+};
+
+/// Collateral of a liquidated position sold at a price decaying every block.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Default, RuntimeDebug)]
+pub struct CollateralAuction<AccountId, AssetId, Balance, BlockNumber> {
+    /// Position the collateral was seized from
+    pub target: AccountId,
+    /// Asset the debt is repaid in
+    pub pay_asset_id: AssetId,
+    /// Asset being sold
+    pub get_asset_id: AssetId,
+    /// Debt still to be raised
+    pub debt: Balance,
+    /// Collateral still for sale
+    pub lot: Balance,
+    /// Pay asset per unit of collateral when the auction starts
+    pub start_price: FixedU128,
+    /// Pay asset per unit of collateral once the auction has fully decayed
+    pub floor_price: FixedU128,
+    /// Block the auction started at
+    pub start: BlockNumber,
+    /// Block the price reaches the floor and the auction is settled
+    pub end: BlockNumber,
+}
+
+impl<AccountId, AssetId, Balance, BlockNumber> CollateralAuction<AccountId, AssetId, Balance, BlockNumber>
+where
+    BlockNumber: AtLeast32BitUnsigned + Copy,
+{
+    /// Price at block `now`, decaying linearly from the start price to the floor.
+    pub fn price_at(&self, now: BlockNumber) -> FixedU128 {
+        if now >= self.end || self.start_price <= self.floor_price {
+            return self.floor_price;
+        }
+        let elapsed = now.saturating_sub(self.start).saturated_into::<u128>();
+        let duration = (self.end - self.start).saturated_into::<u128>();
+        let decayed = FixedU128::saturating_from_rational(elapsed, duration)
+            .saturating_mul(self.start_price - self.floor_price);
+        self.start_price.saturating_sub(decayed)
+    }
+}
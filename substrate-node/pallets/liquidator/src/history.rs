@@ -0,0 +1,31 @@
+use codec::{Decode, Encode};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::{FixedU128, RuntimeDebug};
+
+/// A past liquidation, with the oracle prices it was executed at.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Default, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct LiquidationRecord<AccountId, AssetId, Balance, BlockNumber> {
+    /// Block the liquidation was executed in
+    pub block: BlockNumber,
+    /// Position that was liquidated
+    pub target: AccountId,
+    /// Account that submitted the liquidation
+    pub finder: AccountId,
+    /// Debt asset repaid
+    pub pay_asset_id: AssetId,
+    /// Debt repaid
+    pub repaid: Balance,
+    /// Collateral asset seized
+    pub get_asset_id: AssetId,
+    /// Collateral seized, including the bonus
+    pub seized: Balance,
+    /// Collateral seized on top of the repaid debt value
+    pub bonus: Balance,
+    /// Oracle price of the debt asset
+    pub pay_price: FixedU128,
+    /// Oracle price of the collateral asset
+    pub get_price: FixedU128,
+}
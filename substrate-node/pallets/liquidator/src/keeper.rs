@@ -0,0 +1,156 @@
+//! Offchain worker submitting liquidations of unhealthy positions.
+//!
+//! The keeper signs with the first key of type [`KEY_TYPE`] found in the node keystore,
+//! so a node only runs it once such a key has been inserted.
+
+use super::*;
+use frame_support::{storage::StoragePrefixedMap, Blake2_128Concat, StorageHasher};
+use frame_system::offchain::{SendSignedTransaction, Signer};
+use sp_runtime::offchain::{
+    storage::StorageValueRef,
+    storage_lock::{BlockAndTime, StorageLock},
+    Duration,
+};
+use sp_runtime::KeyTypeId;
+
+/// Key type of the keystore keys signing keeper liquidations.
+pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"liqd");
+
+/// Local storage key of the block the keeper last submitted liquidations at.
+const LAST_RUN_KEY: &[u8] = b"liquidator::keeper::last-run";
+/// Local storage key of the last borrower checked by the previous keeper run.
+const CURSOR_KEY: &[u8] = b"liquidator::keeper::cursor";
+/// Local storage key of the lock held while a keeper run is in progress.
+const LOCK_KEY: &[u8] = b"liquidator::keeper::lock";
+/// Blocks after which a lock left by a crashed run expires.
+const LOCK_BLOCK_EXPIRATION: u32 = 3;
+/// Milliseconds after which a lock left by a crashed run expires.
+const LOCK_TIMEOUT_EXPIRATION: u64 = 10_000;
+
+/// Keeper key crypto, sr25519 signatures verified as the runtime `MultiSignature`.
+pub mod crypto {
+    use super::KEY_TYPE;
+    use sp_runtime::{
+        app_crypto::{app_crypto, sr25519},
+        MultiSignature, MultiSigner,
+    };
+
+    app_crypto!(sr25519, KEY_TYPE);
+
+    pub struct KeeperAuthId;
+
+    impl frame_system::offchain::AppCrypto<MultiSigner, MultiSignature> for KeeperAuthId {
+        type RuntimeAppPublic = Public;
+        type GenericSignature = sp_core::sr25519::Signature;
+        type GenericPublic = sp_core::sr25519::Public;
+    }
+}
+
+impl<T: Config> Pallet<T> {
+    /// Submit liquidations for unhealthy positions, at most once every `KeeperInterval` blocks.
+    pub(crate) fn run_keeper(now: T::BlockNumber) -> Result<(), &'static str> {
+        let signer = Signer::<T, T::AuthorityId>::any_account();
+        if !signer.can_sign() {
+            return Ok(());
+        }
+
+        // Workers of consecutive blocks may overlap, only one of them scans at a time
+        let mut lock = StorageLock::<BlockAndTime<frame_system::Pallet<T>>>::with_block_and_time_deadline(
+            LOCK_KEY,
+            LOCK_BLOCK_EXPIRATION,
+            Duration::from_millis(LOCK_TIMEOUT_EXPIRATION),
+        );
+        let _guard = lock.try_lock().map_err(|_| "keeper already running")?;
+
+        let last_run = StorageValueRef::persistent(LAST_RUN_KEY);
+        let interval = T::KeeperInterval::get();
+        let res = last_run.mutate(|last: Option<Option<T::BlockNumber>>| match last {
+            Some(Some(block)) if now < block.saturating_add(interval) => Err(()),
+            _ => Ok(now),
+        });
+        match res {
+            Ok(Ok(_)) => {}
+            Err(()) => return Ok(()),
+            Ok(Err(_)) => return Err("failed to record keeper run"),
+        }
+
+        // Each run checks the borrowers following the last one checked by the previous run
+        let mut cursor = StorageValueRef::persistent(CURSOR_KEY);
+        let (orders, next) = Self::keeper_orders(cursor.get::<T::AccountId>().flatten());
+        match next {
+            Some(who) => cursor.set(&who),
+            None => cursor.clear(),
+        }
+
+        for (target_user, pay_asset_id, get_asset_id, repay_amount) in orders {
+            let result = signer.send_signed_transaction(|_| {
+                Call::liquidate(target_user.clone(), pay_asset_id, get_asset_id, repay_amount)
+            });
+            match result {
+                Some((_, Ok(()))) => {}
+                _ => return Err("failed to submit liquidation"),
+            }
+        }
+        Ok(())
+    }
+
+    /// Liquidations the keeper submits, one per unhealthy position up to `MaxKeeperLiquidations`.
+    ///
+    /// Only the `MaxKeeperScan` borrowers following `cursor` are checked. Each liquidation repays
+    /// the close factor of the largest debt against the largest collateral by value. Also returns
+    /// the cursor of the next run, `None` once the last borrower has been checked.
+    pub fn keeper_orders(
+        cursor: Option<T::AccountId>,
+    ) -> (Vec<(T::AccountId, AssetIdOf<T>, AssetIdOf<T>, BalanceOf<T>)>, Option<T::AccountId>) {
+        let limit = T::MaxKeeperScan::get() as usize;
+        let targets = Self::borrowers_after(cursor, limit);
+        let next = if targets.len() < limit { None } else { targets.last().cloned() };
+        let orders = targets
+            .into_iter()
+            .filter(|who| Self::health_factor(who).map_or(false, |health| health < FixedU128::one()))
+            .filter_map(|who| {
+                let (pay_asset_id, debt) = Debts::<T>::iter_prefix(&who)
+                    .map(|(asset_id, _)| (asset_id, Self::debt_balance(&who, asset_id)))
+                    .max_by_key(|(asset_id, debt)| Self::asset_value(*asset_id, *debt).unwrap_or_default())?;
+                let (get_asset_id, _) = Collateral::<T>::iter_prefix(&who)
+                    .max_by_key(|(asset_id, amount)| Self::asset_value(*asset_id, *amount).unwrap_or_default())?;
+                let repay_amount = CloseFactor::<T>::get() * debt;
+                if repay_amount.is_zero()
+                    || T::PriceProvider::is_paused(pay_asset_id)
+                    || T::PriceProvider::is_paused(get_asset_id)
+                {
+                    return None;
+                }
+                Some((who, pay_asset_id, get_asset_id, repay_amount))
+            })
+            .take(T::MaxKeeperLiquidations::get() as usize)
+            .collect();
+        (orders, next)
+    }
+
+    /// Up to `limit` borrowers following `cursor` in the storage order of `Debts`.
+    fn borrowers_after(cursor: Option<T::AccountId>, limit: usize) -> Vec<T::AccountId> {
+        let prefix = Debts::<T>::final_prefix();
+        // Debts are keyed by the borrower first, so all debts of a borrower are adjacent
+        let mut key = match &cursor {
+            Some(who) => [&prefix[..], &Blake2_128Concat::hash(&who.encode())].concat(),
+            None => prefix.to_vec(),
+        };
+        let mut borrowers: Vec<T::AccountId> = Vec::new();
+        while borrowers.len() < limit {
+            key = match sp_io::storage::next_key(&key) {
+                Some(next) if next.starts_with(&prefix) => next,
+                _ => break,
+            };
+            // Skip the hash preceding the borrower in its `Blake2_128Concat` key
+            let who = match T::AccountId::decode(&mut &key[prefix.len() + 16..]) {
+                Ok(who) => who,
+                Err(_) => break,
+            };
+            if Some(&who) != cursor.as_ref() && Some(&who) != borrowers.last() {
+                borrowers.push(who);
+            }
+        }
+        borrowers
+    }
+}
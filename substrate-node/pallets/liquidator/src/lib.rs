@@ -1,87 +1,1134 @@
 #![cfg_attr(not(feature = "std"), no_std)]
-use frame_support::{decl_error, decl_event, decl_module, decl_storage, ensure, traits::Get};
-use frame_system::ensure_signed;
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+mod positions;
+pub use positions::{CollateralParams, LiquidationOrder};
+
+mod auction;
+pub use auction::CollateralAuction;
+
+mod history;
+pub use history::LiquidationRecord;
+
+mod keeper;
+pub use keeper::{crypto, KEY_TYPE};
+
+use frame_support::{ensure, traits::{Currency, Get, Imbalance, OnUnbalanced, ReservableCurrency}, dispatch::DispatchResult, weights::Weight};
+use codec::{Decode, Encode};
+use sp_std::{convert::TryInto, prelude::*};
 use sp_runtime::{
-    traits::{AccountIdConversion, Zero},
-    ModuleId,
+    helpers_128bit::multiply_by_rational,
+    traits::{AccountIdConversion, Bounded, CheckedAdd, CheckedDiv, One, Saturating, Zero},
+    FixedPointNumber, FixedU128, PerThing, Permill, RuntimeDebug, SaturatedConversion,
 };
 
-use pallet_assets as Assets;
+use pallet_currencies::MultiCurrency;
 use pallet_vault as Vault;
-use pallet_liquidator_adapter as LiquidatorAdapter;
-pub trait Config: Assets::Config + Vault::Config + LiquidatorAdapter::Config {
-    type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
+use pallet_liquidator_adapter::{self as LiquidatorAdapter, StrategyKind};
+use pallet_oracle::PriceProvider;
+
+pub type AssetIdOf<T> = <T as Vault::Config>::AssetId;
+pub type BalanceOf<T> = <T as Vault::Config>::Balance;
+pub type BondBalanceOf<T> = <<T as Config>::BondCurrency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+pub type AuctionOf<T> = CollateralAuction<
+    <T as frame_system::Config>::AccountId,
+    AssetIdOf<T>,
+    BalanceOf<T>,
+    <T as frame_system::Config>::BlockNumber,
+>;
+pub type LiquidationOrderOf<T> = LiquidationOrder<<T as frame_system::Config>::AccountId, AssetIdOf<T>, BalanceOf<T>>;
+pub type LiquidationRecordOf<T> = LiquidationRecord<
+    <T as frame_system::Config>::AccountId,
+    AssetIdOf<T>,
+    BalanceOf<T>,
+    <T as frame_system::Config>::BlockNumber,
+>;
+pub type NegativeImbalanceOf<T> = <<T as Config>::BondCurrency as Currency<<T as frame_system::Config>::AccountId>>::NegativeImbalance;
 
-    /// The liquidator's module id, used for deriving its sovereign account ID.
-    type LiquidatorModuleId: Get<ModuleId>;
+/// Storage layout versions of the liquidator.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum Releases {
+    /// First FRAME v2 layout
+    V1_0_0,
 }
 
-// 3. Storage
-decl_storage! {
-    trait Store for Module<T: Config> as Liquidator {
-        pub Finders: Vec<T::AccountId>; 
+impl Default for Releases {
+    fn default() -> Self {
+        Releases::V1_0_0
     }
 }
 
-// 4. Events
-decl_event! {
-    pub enum Event<T> where AccountId = <T as frame_system::Config>::AccountId, Balance = <T as Assets::Config>::Balance {
-        /// Event emitted when a Backer deposits
-        VaultDeposit(AccountId, Balance),
-        /// Event emitted when a Backer withdraws
-        VaultWithdraw(AccountId, Balance),
+#[frame_support::pallet]
+pub mod pallet {
+    use super::*;
+    use frame_support::{pallet_prelude::*, PalletId};
+    use frame_system::{offchain::{AppCrypto, CreateSignedTransaction}, pallet_prelude::*};
+    use sp_std::prelude::*;
+
+    #[pallet::config]
+    pub trait Config: CreateSignedTransaction<Call<Self>>
+        + Vault::Config
+        + LiquidatorAdapter::Config<AssetId = AssetIdOf<Self>, Balance = BalanceOf<Self>>
+    {
+        type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+        /// The liquidator's pallet id, used for deriving its sovereign account ID.
+        #[pallet::constant]
+        type LiquidatorPalletId: Get<PalletId>;
+
+        /// Origin allowed to set collateral parameters.
+        type AdminOrigin: EnsureOrigin<Self::Origin>;
+
+        /// Currency reserved as the finder bond.
+        type BondCurrency: ReservableCurrency<Self::AccountId>;
+
+        /// Bond reserved when registering as a finder.
+        #[pallet::constant]
+        type FinderBond: Get<BondBalanceOf<Self>>;
+
+        /// Maximum number of registered finders.
+        #[pallet::constant]
+        type MaxFinders: Get<u32>;
+
+        /// Share of the liquidation bonus paid to the finder.
+        #[pallet::constant]
+        type FinderRewardShare: Get<Permill>;
+
+        /// Handler for slashed finder bonds.
+        type Slash: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+        /// Premium over the oracle price that collateral auctions start at.
+        #[pallet::constant]
+        type AuctionStartPremium: Get<Permill>;
+
+        /// Share of the oracle price that collateral auctions decay to.
+        #[pallet::constant]
+        type AuctionFloor: Get<Permill>;
+
+        /// Blocks for an auction price to decay to the floor, after which it is settled.
+        #[pallet::constant]
+        type AuctionDuration: Get<Self::BlockNumber>;
+
+        /// Maximum number of running auctions.
+        #[pallet::constant]
+        type MaxAuctions: Get<u32>;
+
+        /// Maximum number of orders in a batch liquidation.
+        #[pallet::constant]
+        type MaxBatchLiquidations: Get<u32>;
+
+        /// Number of liquidation records kept, older ones are pruned.
+        #[pallet::constant]
+        type MaxHistory: Get<u32>;
+
+        /// Number of liquidation records indexed per target and per finder.
+        #[pallet::constant]
+        type MaxHistoryPerAccount: Get<u32>;
+
+        /// Keystore key the offchain keeper signs liquidations with.
+        type AuthorityId: AppCrypto<Self::Public, Self::Signature>;
+
+        /// Blocks between two runs of the offchain keeper.
+        #[pallet::constant]
+        type KeeperInterval: Get<Self::BlockNumber>;
+
+        /// Maximum number of liquidations the offchain keeper submits per run.
+        #[pallet::constant]
+        type MaxKeeperLiquidations: Get<u32>;
+
+        /// Maximum number of borrowers the offchain keeper checks per run.
+        #[pallet::constant]
+        type MaxKeeperScan: Get<u32>;
+
+        /// Oracle pricing debt and collateral.
+        type PriceProvider: PriceProvider<AssetIdOf<Self>, BalanceOf<Self>>;
     }
-}
 
-// 5. Errors
-decl_error! {
-    pub enum Error for Module<T: Config> {
-        /// Borrows more than liquidation returns
-        BorrowExceedsLiquidation,
+    #[pallet::pallet]
+    #[pallet::generate_store(pub(super) trait Store)]
+    pub struct Pallet<T>(_);
+
+    /// Accounts allowed to find liquidations, kept sorted
+    #[pallet::storage]
+    #[pallet::getter(fn finders)]
+    pub type Finders<T: Config> = StorageValue<_, Vec<T::AccountId>, ValueQuery>;
+
+    /// Bond currently reserved by a finder
+    #[pallet::storage]
+    #[pallet::getter(fn finder_bond)]
+    pub type FinderBonds<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, BondBalanceOf<T>, ValueQuery>;
+
+    /// Whether only registered finders may liquidate
+    #[pallet::storage]
+    #[pallet::getter(fn finders_only)]
+    pub type FindersOnly<T> = StorageValue<_, bool, ValueQuery>;
+
+    /// Collateral deposited by an account per asset
+    #[pallet::storage]
+    #[pallet::getter(fn collateral)]
+    pub type Collateral<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat, T::AccountId,
+        Blake2_128Concat, AssetIdOf<T>,
+        BalanceOf<T>,
+        ValueQuery,
+    >;
+
+    /// Debt of an account per asset, relative to the vault borrow index
+    #[pallet::storage]
+    #[pallet::getter(fn debts)]
+    pub type Debts<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat, T::AccountId,
+        Blake2_128Concat, AssetIdOf<T>,
+        Vault::BorrowSnapshot<BalanceOf<T>>,
+        ValueQuery,
+    >;
+
+    /// Collateral factor and liquidation threshold of an asset, unset if it is not accepted as collateral
+    #[pallet::storage]
+    #[pallet::getter(fn collateral_params)]
+    pub type CollateralAssets<T: Config> = StorageMap<_, Blake2_128Concat, AssetIdOf<T>, CollateralParams>;
+
+    #[pallet::type_value]
+    pub fn DefaultCloseFactor() -> Permill {
+        Permill::from_percent(50)
     }
-}
 
-// 6. Callable Functions
-decl_module! {
-    pub struct Module<T: Config> for enum Call where origin: T::Origin {
-        // Errors must be initialized if they are used by the pallet.
-        type Error = Error<T>;
+    /// Largest share of a position's debt repayable by a single liquidation
+    #[pallet::storage]
+    #[pallet::getter(fn close_factor)]
+    pub type CloseFactor<T> = StorageValue<_, Permill, ValueQuery, DefaultCloseFactor>;
+
+    /// Collateral seized by liquidations and held by the liquidator account
+    #[pallet::storage]
+    #[pallet::getter(fn seized_collateral)]
+    pub type SeizedCollateral<T: Config> = StorageMap<_, Blake2_128Concat, AssetIdOf<T>, BalanceOf<T>, ValueQuery>;
 
-        // Events must be initialized if they are used by the pallet.
-        fn deposit_event() = default;
+    /// Vault debt repaid by liquidations whose collateral is held, per collateral and debt asset,
+    /// still owed to the vault until the collateral is sold
+    #[pallet::storage]
+    #[pallet::getter(fn held_debt)]
+    pub type HeldDebt<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat, AssetIdOf<T>,
+        Blake2_128Concat, AssetIdOf<T>,
+        BalanceOf<T>,
+        ValueQuery,
+    >;
 
-        const ModuleId: ModuleId = T::LiquidatorModuleId::get();
+    /// Repaid debt of an asset from which liquidations are sold by auction, instant if unset
+    #[pallet::storage]
+    #[pallet::getter(fn auction_threshold)]
+    pub type AuctionThresholds<T: Config> = StorageMap<_, Blake2_128Concat, AssetIdOf<T>, BalanceOf<T>>;
 
-        #[weight = 700_000]
-        pub fn liquidate(origin, target_user: T::AccountId, pay_asset_id: T::AssetId, get_asset_id: T::AssetId, pay_asset_amount: T::Balance, max_liquidatable: T::Balance, borrow_amount: T::Balance) {
+    /// Running collateral auctions
+    #[pallet::storage]
+    #[pallet::getter(fn auctions)]
+    pub type Auctions<T: Config> = StorageMap<_, Twox64Concat, u32, AuctionOf<T>>;
+
+    /// Identifier of the next collateral auction
+    #[pallet::storage]
+    pub(super) type NextAuctionId<T> = StorageValue<_, u32, ValueQuery>;
+
+    /// Running auction ids, oldest first
+    #[pallet::storage]
+    #[pallet::getter(fn active_auctions)]
+    pub type ActiveAuctions<T> = StorageValue<_, Vec<u32>, ValueQuery>;
+
+    /// Debt of an asset left unpaid after its collateral ran out
+    #[pallet::storage]
+    #[pallet::getter(fn bad_debt)]
+    pub type BadDebt<T: Config> = StorageMap<_, Blake2_128Concat, AssetIdOf<T>, BalanceOf<T>, ValueQuery>;
+
+    /// Past liquidations by record id
+    #[pallet::storage]
+    #[pallet::getter(fn liquidation_record)]
+    pub type LiquidationHistory<T: Config> = StorageMap<_, Twox64Concat, u64, LiquidationRecordOf<T>, OptionQuery>;
+
+    /// Id of the next liquidation record
+    #[pallet::storage]
+    pub(super) type NextRecordId<T: Config> = StorageValue<_, u64, ValueQuery>;
+
+    /// Ids of the latest liquidation records of a target, oldest first
+    #[pallet::storage]
+    #[pallet::getter(fn target_history_ids)]
+    pub type TargetHistory<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, Vec<u64>, ValueQuery>;
+
+    /// Ids of the latest liquidation records of a finder, oldest first
+    #[pallet::storage]
+    #[pallet::getter(fn finder_history_ids)]
+    pub type FinderHistory<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, Vec<u64>, ValueQuery>;
+
+    /// Storage layout version, used to migrate from older releases
+    #[pallet::storage]
+    pub(super) type StorageVersion<T> = StorageValue<_, Releases, ValueQuery>;
+
+    #[pallet::genesis_config]
+    pub struct GenesisConfig<T: Config> {
+        /// Finders trusted at genesis, registered without a bond
+        pub finders: Vec<T::AccountId>,
+        pub finders_only: bool,
+    }
+
+    #[cfg(feature = "std")]
+    impl<T: Config> Default for GenesisConfig<T> {
+        fn default() -> Self {
+            Self { finders: Vec::new(), finders_only: false }
+        }
+    }
+
+    #[pallet::genesis_build]
+    impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+        fn build(&self) {
+            let mut finders = self.finders.clone();
+            finders.sort();
+            finders.dedup();
+            assert!(finders.len() as u32 <= T::MaxFinders::get(), "too many genesis finders");
+            Finders::<T>::put(finders);
+            FindersOnly::<T>::put(self.finders_only);
+            StorageVersion::<T>::put(Releases::V1_0_0);
+        }
+    }
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn on_initialize(now: T::BlockNumber) -> Weight {
+            Self::settle_expired_auctions(now)
+        }
+
+        fn offchain_worker(now: T::BlockNumber) {
+            if let Err(e) = Self::run_keeper(now) {
+                log::warn!(target: "liquidator", "keeper run at block {:?} failed: {}", now, e);
+            }
+        }
+    }
+
+    #[pallet::event]
+    #[pallet::metadata(
+        T::AccountId = "AccountId",
+        AssetIdOf<T> = "AssetId",
+        BalanceOf<T> = "Balance",
+        BondBalanceOf<T> = "Balance"
+    )]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
+        /// Event emitted when a position is liquidated [finder, target_user, pay_asset_id, repaid, get_asset_id, seized, bonus]
+        Liquidated(T::AccountId, T::AccountId, AssetIdOf<T>, BalanceOf<T>, AssetIdOf<T>, BalanceOf<T>, BalanceOf<T>),
+        /// Event emitted when collateral is deposited [who, asset_id, amount]
+        CollateralDeposited(T::AccountId, AssetIdOf<T>, BalanceOf<T>),
+        /// Event emitted when collateral is withdrawn [who, asset_id, amount]
+        CollateralWithdrawn(T::AccountId, AssetIdOf<T>, BalanceOf<T>),
+        /// Event emitted when a position borrows [who, asset_id, amount]
+        PositionBorrowed(T::AccountId, AssetIdOf<T>, BalanceOf<T>),
+        /// Event emitted when a position repays [who, asset_id, amount]
+        PositionRepaid(T::AccountId, AssetIdOf<T>, BalanceOf<T>),
+        /// Event emitted when collateral parameters are set [asset_id]
+        CollateralParamsSet(AssetIdOf<T>),
+        /// Event emitted when the close factor is set [close_factor]
+        CloseFactorSet(Permill),
+        /// Event emitted when a finder registers [finder, bond]
+        FinderRegistered(T::AccountId, BondBalanceOf<T>),
+        /// Event emitted when a finder unregisters [finder, bond_returned]
+        FinderUnregistered(T::AccountId, BondBalanceOf<T>),
+        /// Event emitted when a finder bond is slashed [finder, slashed]
+        FinderSlashed(T::AccountId, BondBalanceOf<T>),
+        /// Event emitted when a finder is paid part of the liquidation bonus [finder, asset_id, reward]
+        FinderRewarded(T::AccountId, AssetIdOf<T>, BalanceOf<T>),
+        /// Event emitted when liquidations are restricted to finders or opened to everyone [finders_only]
+        FindersOnlySet(bool),
+        /// Event emitted when collateral is put up for auction [auction_id, target_user, get_asset_id, lot, pay_asset_id, debt]
+        AuctionStarted(u32, T::AccountId, AssetIdOf<T>, BalanceOf<T>, AssetIdOf<T>, BalanceOf<T>),
+        /// Event emitted when part of a lot is bought [auction_id, bidder, collateral, paid]
+        AuctionBid(u32, T::AccountId, BalanceOf<T>, BalanceOf<T>),
+        /// Event emitted when an auction ends [auction_id, lot_left, debt_left]
+        AuctionClosed(u32, BalanceOf<T>, BalanceOf<T>),
+        /// Event emitted when debt is left uncovered [asset_id, amount]
+        BadDebtRecorded(AssetIdOf<T>, BalanceOf<T>),
+        /// Event emitted when the auction threshold of an asset is set [asset_id, threshold]
+        AuctionThresholdSet(AssetIdOf<T>, Option<BalanceOf<T>>),
+        /// Event emitted when an order of a best-effort batch fails [index, target_user, error]
+        BatchOrderFailed(u32, T::AccountId, DispatchError),
+        /// Event emitted when a batch liquidation completes [finder, succeeded]
+        BatchLiquidated(T::AccountId, u32),
+        /// Event emitted when held collateral is sold to repay the vault [get_asset_id, amount, pay_asset_id, received, repaid]
+        SeizedCollateralSold(AssetIdOf<T>, BalanceOf<T>, AssetIdOf<T>, BalanceOf<T>, BalanceOf<T>),
+    }
+
+    #[pallet::error]
+    pub enum Error<T> {
+        /// Repaying more than the close factor allows
+        ExceedCloseFactor,
+        /// Target health factor is not below one
+        PositionHealthy,
+        /// Target has no debt in the pay asset
+        NoDebt,
+        /// Target has no collateral in the seized asset
+        NoCollateral,
+        /// Asset is not accepted as collateral
+        NotCollateral,
+        /// Collateral factor is above the liquidation threshold
+        InvalidCollateralParams,
+        /// Withdrawing more collateral than deposited
+        ExceedCollateral,
+        /// Repaying more than the outstanding debt
+        ExceedRepayAmount,
+        /// Position would not be covered by its collateral
+        Undercollateralized,
+        /// Zero Amount
+        ZeroAmount,
+        /// Transfer of collateral or debt failed
+        TransferFailed,
+        /// Close factor must be above zero
+        InvalidCloseFactor,
+        /// Oracle has no fresh price for an asset of the position
+        NoPrice,
+        /// Liquidations are restricted to registered finders
+        NotFinder,
+        /// Account is already a registered finder
+        AlreadyFinder,
+        /// Finder set is full
+        TooManyFinders,
+        /// Auction does not exist
+        UnknownAuction,
+        /// Too many auctions are running
+        TooManyAuctions,
+        /// Bid would buy nothing or pay nothing
+        BidTooLow,
+        /// Arithmetic overflow
+        Overflow,
+        /// Batch has more orders than allowed
+        TooManyOrders,
+        /// Selling the seized collateral did not raise the repaid debt
+        LoanNotRepaid,
+        /// Oracle circuit breaker of an asset of the liquidation is tripped
+        LiquidationsPaused,
+    }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        // Repay part of the debt of an unhealthy position and seize its collateral with a bonus
+        //
+        // @param target_user The position being liquidated
+        // @param pay_asset_id The debt asset being repaid
+        // @param get_asset_id The collateral asset being seized
+        // @param repay_amount The debt to repay, at most the close factor of the target's debt
+        #[pallet::weight(700_000)]
+        pub fn liquidate(
+            origin: OriginFor<T>,
+            target_user: T::AccountId,
+            pay_asset_id: AssetIdOf<T>,
+            get_asset_id: AssetIdOf<T>,
+            repay_amount: BalanceOf<T>,
+        ) -> DispatchResultWithPostInfo {
             // Check that the extrinsic was signed and get the signer.
             // This function will return an error if the extrinsic is not signed.
             // https://substrate.dev/docs/en/knowledgebase/runtime/origin
             let sender = ensure_signed(origin)?;
-            let origin_account = sender.clone();
+            Self::do_liquidate(sender, target_user, pay_asset_id, get_asset_id, repay_amount)?;
+            Ok(().into())
+        }
+
+        // Liquidate several positions in one transaction
+        //
+        // @param orders The liquidations to execute, in order
+        // @param all_or_nothing Revert the whole batch if any order fails, otherwise skip failed orders
+        #[pallet::weight(700_000u64.saturating_mul(orders.len() as Weight))]
+        #[frame_support::transactional]
+        pub fn liquidate_batch(
+            origin: OriginFor<T>,
+            orders: Vec<LiquidationOrderOf<T>>,
+            all_or_nothing: bool,
+        ) -> DispatchResultWithPostInfo {
+            let sender = ensure_signed(origin)?;
+            ensure!(orders.len() as u32 <= T::MaxBatchLiquidations::get(), Error::<T>::TooManyOrders);
+
+            let mut succeeded = 0u32;
+            for (index, order) in orders.into_iter().enumerate() {
+                let result = Self::do_liquidate(
+                    sender.clone(),
+                    order.target.clone(),
+                    order.pay_asset_id,
+                    order.get_asset_id,
+                    order.repay_amount,
+                );
+                match result {
+                    Ok(()) => succeeded += 1,
+                    Err(e) if all_or_nothing => return Err(e.into()),
+                    Err(e) => Self::deposit_event(Event::BatchOrderFailed(index as u32, order.target, e)),
+                }
+            }
+            Self::deposit_event(Event::BatchLiquidated(sender, succeeded));
+            Ok(().into())
+        }
+
+        // Set the share of a position's debt that a single liquidation may repay
+        #[pallet::weight(700_000)]
+        pub fn set_close_factor(origin: OriginFor<T>, close_factor: Permill) -> DispatchResultWithPostInfo {
+            <T as Config>::AdminOrigin::ensure_origin(origin)?;
+            ensure!(!close_factor.is_zero(), Error::<T>::InvalidCloseFactor);
+
+            CloseFactor::<T>::put(close_factor);
+
+            Self::deposit_event(Event::CloseFactorSet(close_factor));
+            Ok(().into())
+        }
+
+        // Reserve the finder bond and join the finder set
+        #[pallet::weight(700_000)]
+        pub fn register_finder(origin: OriginFor<T>) -> DispatchResultWithPostInfo {
+            let sender = ensure_signed(origin)?;
+            let mut finders = Finders::<T>::get();
+            let index = match finders.binary_search(&sender) {
+                Ok(_) => return Err(Error::<T>::AlreadyFinder.into()),
+                Err(index) => index,
+            };
+            ensure!((finders.len() as u32) < T::MaxFinders::get(), Error::<T>::TooManyFinders);
+
+            let bond = T::FinderBond::get();
+            T::BondCurrency::reserve(&sender, bond)?;
+            finders.insert(index, sender.clone());
+            Finders::<T>::put(finders);
+            FinderBonds::<T>::insert(&sender, bond);
+
+            Self::deposit_event(Event::FinderRegistered(sender, bond));
+            Ok(().into())
+        }
+
+        // Leave the finder set and get the remaining bond back
+        #[pallet::weight(700_000)]
+        pub fn unregister_finder(origin: OriginFor<T>) -> DispatchResultWithPostInfo {
+            let sender = ensure_signed(origin)?;
+            ensure!(Self::remove_finder(&sender), Error::<T>::NotFinder);
+
+            let bond = FinderBonds::<T>::take(&sender);
+            T::BondCurrency::unreserve(&sender, bond);
+
+            Self::deposit_event(Event::FinderUnregistered(sender, bond));
+            Ok(().into())
+        }
+
+        // Slash the bond of a finder whose liquidation was proven invalid
+        //
+        // A finder left with less than the required bond is removed from the set.
+        #[pallet::weight(700_000)]
+        pub fn slash_finder(origin: OriginFor<T>, finder: T::AccountId, amount: BondBalanceOf<T>) -> DispatchResultWithPostInfo {
+            <T as Config>::AdminOrigin::ensure_origin(origin)?;
+            let bond = FinderBonds::<T>::get(&finder);
+            ensure!(Self::is_finder(&finder), Error::<T>::NotFinder);
+
+            // The reserve may hold less than the bond if it was reduced elsewhere
+            let (imbalance, _) = T::BondCurrency::slash_reserved(&finder, amount.min(bond));
+            let slashed = imbalance.peek();
+            T::Slash::on_unbalanced(imbalance);
+            let remaining = bond.saturating_sub(slashed);
+
+            if remaining < T::FinderBond::get() {
+                Self::remove_finder(&finder);
+                FinderBonds::<T>::remove(&finder);
+                T::BondCurrency::unreserve(&finder, remaining);
+            } else {
+                FinderBonds::<T>::insert(&finder, remaining);
+            }
+
+            Self::deposit_event(Event::FinderSlashed(finder, slashed));
+            Ok(().into())
+        }
+
+        // Buy up to `max_lot` of an auctioned collateral at the current auction price
+        //
+        // The payment repays the vault. Once the debt is raised the rest of the lot
+        // returns to the liquidated position.
+        #[pallet::weight(700_000)]
+        #[frame_support::transactional]
+        pub fn bid(origin: OriginFor<T>, auction_id: u32, max_lot: BalanceOf<T>) -> DispatchResultWithPostInfo {
+            let sender = ensure_signed(origin)?;
+            ensure!(!max_lot.is_zero(), Error::<T>::ZeroAmount);
+            let mut auction = Auctions::<T>::get(auction_id).ok_or(Error::<T>::UnknownAuction)?;
+
+            // The vault may be owed less than the auction debt once debt was written off
+            let liquidator = Self::account_id();
+            <Vault::Pallet<T>>::accrue_interest(auction.pay_asset_id);
+            auction.debt = auction.debt.min(<Vault::Pallet<T>>::borrow_balance(auction.pay_asset_id, &liquidator));
+
+            let price = auction.price_at(<frame_system::Pallet<T>>::block_number());
+            let mut take = max_lot.min(auction.lot);
+            let mut cost: BalanceOf<T> = price.saturating_mul_int(take.saturated_into::<u128>()).saturated_into();
+            if cost > auction.debt {
+                cost = auction.debt;
+                take = Self::lot_for(cost, price).min(auction.lot);
+            }
+            ensure!(!take.is_zero() && !cost.is_zero(), Error::<T>::BidTooLow);
+
+            <T as Vault::Config>::Currencies::transfer(auction.pay_asset_id, &sender, &liquidator, cost)
+                .map_err(|_| Error::<T>::TransferFailed)?;
+            <Vault::Pallet<T>>::do_repay(&liquidator, auction.pay_asset_id, cost)?;
+            <T as Vault::Config>::Currencies::transfer(auction.get_asset_id, &liquidator, &sender, take)
+                .map_err(|_| Error::<T>::TransferFailed)?;
+
+            auction.debt -= cost;
+            auction.lot -= take;
+            Self::deposit_event(Event::AuctionBid(auction_id, sender, take, cost));
+
+            if auction.debt.is_zero() || auction.lot.is_zero() {
+                Self::close_auction(auction_id, auction);
+            } else {
+                Auctions::<T>::insert(auction_id, auction);
+            }
+            Ok(().into())
+        }
+
+        // Sell `amount` of the collateral held by the liquidator on the swap market and repay
+        // the vault debt its liquidations left outstanding
+        //
+        // Proceeds above that debt stay with the liquidator. Debt still held against the asset
+        // once all of it is sold is written off as bad debt.
+        #[pallet::weight(700_000)]
+        #[frame_support::transactional]
+        pub fn sell_seized_collateral(
+            origin: OriginFor<T>,
+            get_asset_id: AssetIdOf<T>,
+            pay_asset_id: AssetIdOf<T>,
+            amount: BalanceOf<T>,
+            min_received: BalanceOf<T>,
+        ) -> DispatchResultWithPostInfo {
+            <T as Config>::AdminOrigin::ensure_origin(origin)?;
+            ensure!(!amount.is_zero(), Error::<T>::ZeroAmount);
+            let seized = SeizedCollateral::<T>::get(get_asset_id);
+            ensure!(amount <= seized, Error::<T>::ExceedCollateral);
+
+            let liquidator = Self::account_id();
+            let received = <LiquidatorAdapter::Pallet<T>>::do_sell(&liquidator, pay_asset_id, get_asset_id, amount, min_received)?;
+            SeizedCollateral::<T>::insert(get_asset_id, seized - amount);
+
+            <Vault::Pallet<T>>::accrue_interest(pay_asset_id);
+            let owed = HeldDebt::<T>::get(get_asset_id, pay_asset_id)
+                .min(<Vault::Pallet<T>>::borrow_balance(pay_asset_id, &liquidator));
+            let repaid = received.min(owed);
+            if !repaid.is_zero() {
+                <Vault::Pallet<T>>::do_repay(&liquidator, pay_asset_id, repaid)?;
+                HeldDebt::<T>::mutate(get_asset_id, pay_asset_id, |debt| *debt -= repaid);
+            }
+            if SeizedCollateral::<T>::get(get_asset_id).is_zero() {
+                for (asset_id, debt) in HeldDebt::<T>::drain_prefix(get_asset_id) {
+                    if !debt.is_zero() {
+                        Self::record_bad_debt(asset_id, debt);
+                    }
+                }
+            }
+
+            Self::deposit_event(Event::SeizedCollateralSold(get_asset_id, amount, pay_asset_id, received, repaid));
+            Ok(().into())
+        }
+
+        // Sell liquidations repaying at least `threshold` of an asset by auction, or always instantly if `None`
+        #[pallet::weight(700_000)]
+        pub fn set_auction_threshold(
+            origin: OriginFor<T>,
+            asset_id: AssetIdOf<T>,
+            threshold: Option<BalanceOf<T>>,
+        ) -> DispatchResultWithPostInfo {
+            <T as Config>::AdminOrigin::ensure_origin(origin)?;
+
+            AuctionThresholds::<T>::set(asset_id, threshold);
+
+            Self::deposit_event(Event::AuctionThresholdSet(asset_id, threshold));
+            Ok(().into())
+        }
+
+        // Restrict liquidations to registered finders, or open them to everyone
+        #[pallet::weight(700_000)]
+        pub fn set_finders_only(origin: OriginFor<T>, finders_only: bool) -> DispatchResultWithPostInfo {
+            <T as Config>::AdminOrigin::ensure_origin(origin)?;
+
+            FindersOnly::<T>::put(finders_only);
+
+            Self::deposit_event(Event::FindersOnlySet(finders_only));
+            Ok(().into())
+        }
+
+        // Accept an asset as collateral, or update its parameters
+        #[pallet::weight(700_000)]
+        pub fn set_collateral_params(
+            origin: OriginFor<T>,
+            asset_id: AssetIdOf<T>,
+            params: CollateralParams,
+        ) -> DispatchResultWithPostInfo {
+            <T as Config>::AdminOrigin::ensure_origin(origin)?;
+            ensure!(params.is_valid(), Error::<T>::InvalidCollateralParams);
+
+            CollateralAssets::<T>::insert(asset_id, params);
+
+            Self::deposit_event(Event::CollateralParamsSet(asset_id));
+            Ok(().into())
+        }
+
+        #[pallet::weight(700_000)]
+        #[frame_support::transactional]
+        pub fn deposit_collateral(origin: OriginFor<T>, asset_id: AssetIdOf<T>, amount: BalanceOf<T>) -> DispatchResultWithPostInfo {
+            let sender = ensure_signed(origin)?;
+            ensure!(!amount.is_zero(), Error::<T>::ZeroAmount);
+            ensure!(CollateralAssets::<T>::contains_key(asset_id), Error::<T>::NotCollateral);
+
+            let total = Collateral::<T>::get(&sender, asset_id).checked_add(&amount).ok_or(Error::<T>::Overflow)?;
+            <T as Vault::Config>::Currencies::transfer(asset_id, &sender, &Self::account_id(), amount)
+                .map_err(|_| Error::<T>::TransferFailed)?;
+            Collateral::<T>::insert(&sender, asset_id, total);
+
+            Self::deposit_event(Event::CollateralDeposited(sender, asset_id, amount));
+            Ok(().into())
+        }
+
+        #[pallet::weight(700_000)]
+        #[frame_support::transactional]
+        pub fn withdraw_collateral(origin: OriginFor<T>, asset_id: AssetIdOf<T>, amount: BalanceOf<T>) -> DispatchResultWithPostInfo {
+            let sender = ensure_signed(origin)?;
+            ensure!(!amount.is_zero(), Error::<T>::ZeroAmount);
+            let held = Collateral::<T>::get(&sender, asset_id);
+            ensure!(held >= amount, Error::<T>::ExceedCollateral);
+
+            Self::set_collateral(&sender, asset_id, held - amount);
+            Self::ensure_collateralized(&sender)?;
+            <T as Vault::Config>::Currencies::transfer(asset_id, &Self::account_id(), &sender, amount)
+                .map_err(|_| Error::<T>::TransferFailed)?;
+
+            Self::deposit_event(Event::CollateralWithdrawn(sender, asset_id, amount));
+            Ok(().into())
+        }
+
+        // Borrow from the vault against the deposited collateral
+        #[pallet::weight(700_000)]
+        #[frame_support::transactional]
+        pub fn borrow(origin: OriginFor<T>, asset_id: AssetIdOf<T>, amount: BalanceOf<T>) -> DispatchResultWithPostInfo {
+            let sender = ensure_signed(origin)?;
+            ensure!(!amount.is_zero(), Error::<T>::ZeroAmount);
+
+            // The liquidator account holds the vault debt on behalf of every position
+            let liquidator = Self::account_id();
+            <Vault::Pallet<T>>::do_borrow(&liquidator, asset_id, amount)?;
+
+            let debt = Self::debt_balance(&sender, asset_id).checked_add(&amount).ok_or(Error::<T>::Overflow)?;
+            Self::set_debt(&sender, asset_id, debt);
+            Self::ensure_collateralized(&sender)?;
+            <T as Vault::Config>::Currencies::transfer(asset_id, &liquidator, &sender, amount)
+                .map_err(|_| Error::<T>::TransferFailed)?;
+
+            Self::deposit_event(Event::PositionBorrowed(sender, asset_id, amount));
+            Ok(().into())
+        }
+
+        #[pallet::weight(700_000)]
+        #[frame_support::transactional]
+        pub fn repay(origin: OriginFor<T>, asset_id: AssetIdOf<T>, amount: BalanceOf<T>) -> DispatchResultWithPostInfo {
+            let sender = ensure_signed(origin)?;
+            ensure!(!amount.is_zero(), Error::<T>::ZeroAmount);
+            <Vault::Pallet<T>>::accrue_interest(asset_id);
+            let debt = Self::debt_balance(&sender, asset_id);
+            ensure!(debt >= amount, Error::<T>::ExceedRepayAmount);
+
+            let liquidator = Self::account_id();
+            <T as Vault::Config>::Currencies::transfer(asset_id, &sender, &liquidator, amount)
+                .map_err(|_| Error::<T>::TransferFailed)?;
+            // Position debt accrues at the vault rate, so it never exceeds what the vault is owed
+            let owed = <Vault::Pallet<T>>::borrow_balance(asset_id, &liquidator);
+            <Vault::Pallet<T>>::do_repay(&liquidator, asset_id, amount.min(owed))?;
+            Self::set_debt(&sender, asset_id, debt - amount);
 
-            let pay_asset_price = <LiquidatorAdapter::Module<T>>::asset_price_adapter(pay_asset_id);
-            ensure!(pay_asset_price * pay_asset_amount > borrow_amount, Error::<T>::BorrowExceedsLiquidation);
-            <Vault::Module<T>>::borrow(frame_system::RawOrigin::Signed(Self::account_id()).into(), pay_asset_id, borrow_amount);
-            <LiquidatorAdapter::Module<T>>::liquidate(frame_system::RawOrigin::Signed(Self::account_id()).into(), target_user, pay_asset_id, get_asset_id, pay_asset_amount);
-            // Emit an event that the deposit went through.
-            Self::deposit_event(RawEvent::VaultDeposit(sender, amount));
+            Self::deposit_event(Event::PositionRepaid(sender, asset_id, amount));
+            Ok(().into())
         }
     }
 }
 
-impl<T: Config> Module<T> {
+impl<T: Config> Pallet<T> {
     // Add public immutables and private mutables.
 
-    /// The account ID of the vault.
+    /// The account ID of the liquidator.
     ///
     /// This actually does computation. If you need to keep using it, then make sure you cache the
     /// value and only call this once.
     pub fn account_id() -> T::AccountId {
-        T::LiquidatorModuleId::get().into_account()
+        <T as Config>::LiquidatorPalletId::get().into_account()
     }
 
     pub fn is_finder(finder: &T::AccountId) -> bool {
-        Finders::<T>::get().binary_search(&finder).is_ok()
+        Finders::<T>::get().binary_search(finder).is_ok()
+    }
+
+    /// Repay `repay_amount` of the debt of an unhealthy position on behalf of `finder`.
+    #[frame_support::transactional]
+    pub fn do_liquidate(
+        finder: T::AccountId,
+        target_user: T::AccountId,
+        pay_asset_id: AssetIdOf<T>,
+        get_asset_id: AssetIdOf<T>,
+        repay_amount: BalanceOf<T>,
+    ) -> DispatchResult {
+        ensure!(!FindersOnly::<T>::get() || Self::is_finder(&finder), Error::<T>::NotFinder);
+        ensure!(
+            !T::PriceProvider::is_paused(pay_asset_id) && !T::PriceProvider::is_paused(get_asset_id),
+            Error::<T>::LiquidationsPaused
+        );
+        ensure!(!repay_amount.is_zero(), Error::<T>::ZeroAmount);
+
+        <Vault::Pallet<T>>::accrue_interest(pay_asset_id);
+        let debt = Self::debt_balance(&target_user, pay_asset_id);
+        ensure!(!debt.is_zero(), Error::<T>::NoDebt);
+        let collateral = Collateral::<T>::get(&target_user, get_asset_id);
+        ensure!(!collateral.is_zero(), Error::<T>::NoCollateral);
+        ensure!(Self::health_factor(&target_user)? < FixedU128::one(), Error::<T>::PositionHealthy);
+        ensure!(repay_amount <= CloseFactor::<T>::get().mul_ceil(debt), Error::<T>::ExceedCloseFactor);
+
+        let (mut seized, mut bonus) = Self::seize_amount(pay_asset_id, get_asset_id, repay_amount)?;
+        let mut repaid = repay_amount;
+        if seized > collateral {
+            // Underwater positions give up all of the collateral for the debt it is worth
+            repaid = Self::scale(repay_amount, collateral, seized)?;
+            bonus = Self::scale(bonus, collateral, seized)?;
+            seized = collateral;
+        }
+
+        Self::set_debt(&target_user, pay_asset_id, debt - repaid);
+        Self::set_collateral(&target_user, get_asset_id, collateral - seized);
+
+        // The finder keeps part of the bonus, the rest of the collateral is held for sale
+        let liquidator = Self::account_id();
+        let reward = T::FinderRewardShare::get() * bonus;
+        if !reward.is_zero() {
+            <T as Vault::Config>::Currencies::transfer(get_asset_id, &liquidator, &finder, reward)
+                .map_err(|_| Error::<T>::TransferFailed)?;
+            Self::deposit_event(Event::FinderRewarded(finder.clone(), get_asset_id, reward));
+        }
+        let lot = seized - reward;
+
+        match AuctionThresholds::<T>::get(pay_asset_id) {
+            // Large liquidations sell the collateral by auction instead of at the fixed bonus
+            Some(threshold) if repaid >= threshold => {
+                Self::start_auction(target_user.clone(), pay_asset_id, repaid, get_asset_id, lot)?;
+            }
+            _ => {
+                // Collateral sold right away must raise the repaid debt, held collateral owes it
+                // to the vault until it is sold
+                let strategy = <LiquidatorAdapter::Pallet<T>>::strategy(pay_asset_id, get_asset_id);
+                let min_received = match strategy {
+                    StrategyKind::HoldForSale => Zero::zero(),
+                    StrategyKind::DirectSwap => repaid,
+                };
+                let received = <LiquidatorAdapter::Pallet<T>>::do_liquidate(
+                    &liquidator,
+                    pay_asset_id,
+                    get_asset_id,
+                    lot,
+                    min_received,
+                )?;
+                let proceeds = received.min(repaid);
+                if !proceeds.is_zero() {
+                    <Vault::Pallet<T>>::do_repay(&liquidator, pay_asset_id, proceeds)?;
+                }
+                let outstanding = repaid - proceeds;
+                if !outstanding.is_zero() {
+                    ensure!(strategy == StrategyKind::HoldForSale, Error::<T>::LoanNotRepaid);
+                    SeizedCollateral::<T>::try_mutate(get_asset_id, |total| -> DispatchResult {
+                        *total = total.checked_add(&lot).ok_or(Error::<T>::Overflow)?;
+                        Ok(())
+                    })?;
+                    HeldDebt::<T>::try_mutate(get_asset_id, pay_asset_id, |debt| -> DispatchResult {
+                        *debt = debt.checked_add(&outstanding).ok_or(Error::<T>::Overflow)?;
+                        Ok(())
+                    })?;
+                }
+            }
+        }
+        Self::write_off_if_insolvent(&target_user);
+
+        Self::record_liquidation(LiquidationRecord {
+            block: <frame_system::Pallet<T>>::block_number(),
+            target: target_user.clone(),
+            finder: finder.clone(),
+            pay_asset_id,
+            repaid,
+            get_asset_id,
+            seized,
+            bonus,
+            pay_price: T::PriceProvider::price(pay_asset_id).unwrap_or_default(),
+            get_price: T::PriceProvider::price(get_asset_id).unwrap_or_default(),
+        });
+
+        // Emit an event that the liquidation went through.
+        Self::deposit_event(Event::Liquidated(finder, target_user, pay_asset_id, repaid, get_asset_id, seized, bonus));
+        Ok(())
+    }
+
+    /// Store a liquidation record, pruning the oldest once `MaxHistory` records are kept.
+    fn record_liquidation(record: LiquidationRecordOf<T>) {
+        let id = NextRecordId::<T>::mutate(|next| {
+            let id = *next;
+            *next = next.saturating_add(1);
+            id
+        });
+        let max_per_account = T::MaxHistoryPerAccount::get() as usize;
+        let index = |ids: &mut Vec<u64>| {
+            ids.push(id);
+            if ids.len() > max_per_account {
+                ids.remove(0);
+            }
+        };
+        TargetHistory::<T>::mutate(&record.target, index);
+        FinderHistory::<T>::mutate(&record.finder, index);
+        LiquidationHistory::<T>::insert(id, record);
+
+        if let Some(pruned_id) = id.checked_sub(T::MaxHistory::get().into()) {
+            if let Some(pruned) = LiquidationHistory::<T>::take(pruned_id) {
+                TargetHistory::<T>::mutate(&pruned.target, |ids| ids.retain(|i| *i != pruned_id));
+                FinderHistory::<T>::mutate(&pruned.finder, |ids| ids.retain(|i| *i != pruned_id));
+            }
+        }
+    }
+
+    /// Liquidation records of a target, newest first, skipping `offset` and returning at most `limit`.
+    pub fn target_history(who: &T::AccountId, offset: u32, limit: u32) -> Vec<LiquidationRecordOf<T>> {
+        Self::page_history(TargetHistory::<T>::get(who), offset, limit)
+    }
+
+    /// Liquidation records of a finder, newest first, skipping `offset` and returning at most `limit`.
+    pub fn finder_history(who: &T::AccountId, offset: u32, limit: u32) -> Vec<LiquidationRecordOf<T>> {
+        Self::page_history(FinderHistory::<T>::get(who), offset, limit)
+    }
+
+    fn page_history(ids: Vec<u64>, offset: u32, limit: u32) -> Vec<LiquidationRecordOf<T>> {
+        ids.into_iter()
+            .rev()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .filter_map(LiquidationHistory::<T>::get)
+            .collect()
+    }
+
+    /// Put `lot` of collateral up for auction to raise `debt` of the pay asset.
+    fn start_auction(
+        target: T::AccountId,
+        pay_asset_id: AssetIdOf<T>,
+        debt: BalanceOf<T>,
+        get_asset_id: AssetIdOf<T>,
+        lot: BalanceOf<T>,
+    ) -> DispatchResult {
+        let mut active = ActiveAuctions::<T>::get();
+        ensure!((active.len() as u32) < T::MaxAuctions::get(), Error::<T>::TooManyAuctions);
+
+        // Pay asset per unit of collateral at oracle prices
+        let lot_worth = T::PriceProvider::amount(pay_asset_id, Self::asset_value(get_asset_id, lot)?)
+            .ok_or(Error::<T>::NoPrice)?;
+        let oracle_price = FixedU128::checked_from_rational(lot_worth.saturated_into::<u128>(), lot.saturated_into::<u128>())
+            .ok_or(Error::<T>::NoPrice)?;
+        let start_price = oracle_price.saturating_add(oracle_price.saturating_mul(T::AuctionStartPremium::get().into()));
+        let floor_price = oracle_price.saturating_mul(T::AuctionFloor::get().into());
+
+        let now = <frame_system::Pallet<T>>::block_number();
+        let auction_id = NextAuctionId::<T>::mutate(|id| {
+            let current = *id;
+            *id = id.wrapping_add(1);
+            current
+        });
+        Auctions::<T>::insert(auction_id, CollateralAuction {
+            target: target.clone(),
+            pay_asset_id,
+            get_asset_id,
+            debt,
+            lot,
+            start_price,
+            floor_price,
+            start: now,
+            end: now.saturating_add(T::AuctionDuration::get()),
+        });
+        active.push(auction_id);
+        ActiveAuctions::<T>::put(active);
+
+        Self::deposit_event(Event::AuctionStarted(auction_id, target, get_asset_id, lot, pay_asset_id, debt));
+        Ok(())
+    }
+
+    /// Collateral bought by paying `cost` at `price`, rounded down.
+    fn lot_for(cost: BalanceOf<T>, price: FixedU128) -> BalanceOf<T> {
+        FixedU128::checked_from_integer(cost.saturated_into::<u128>())
+            .and_then(|cost| cost.checked_div(&price))
+            .map(|lot| (lot.into_inner() / FixedU128::accuracy()).saturated_into())
+            .unwrap_or_else(Zero::zero)
+    }
+
+    /// End an auction, returning the remaining lot to the position if its debt was raised.
+    ///
+    /// Otherwise the lot is kept as seized collateral and the remaining debt is recorded as bad debt.
+    fn close_auction(auction_id: u32, auction: AuctionOf<T>) {
+        Auctions::<T>::remove(auction_id);
+        ActiveAuctions::<T>::mutate(|active| active.retain(|id| *id != auction_id));
+
+        if auction.debt.is_zero() {
+            Collateral::<T>::mutate(&auction.target, auction.get_asset_id, |held| *held = held.saturating_add(auction.lot));
+        } else if auction.lot.is_zero() {
+            Self::record_bad_debt(auction.pay_asset_id, auction.debt);
+        } else {
+            // The unsold lot is held against the debt until governance sells it
+            SeizedCollateral::<T>::mutate(auction.get_asset_id, |total| *total = total.saturating_add(auction.lot));
+            HeldDebt::<T>::mutate(auction.get_asset_id, auction.pay_asset_id, |debt| *debt = debt.saturating_add(auction.debt));
+        }
+
+        Self::deposit_event(Event::AuctionClosed(auction_id, auction.lot, auction.debt));
+    }
+
+    /// Record `amount` of debt that will not be repaid and write it off in the vault.
+    fn record_bad_debt(asset_id: AssetIdOf<T>, amount: BalanceOf<T>) {
+        BadDebt::<T>::mutate(asset_id, |total| *total = total.saturating_add(amount));
+        <Vault::Pallet<T>>::record_bad_debt(&Self::account_id(), asset_id, amount);
+        Self::deposit_event(Event::BadDebtRecorded(asset_id, amount));
+    }
+
+    /// Write off the remaining debt of a position that has no collateral left.
+    fn write_off_if_insolvent(who: &T::AccountId) {
+        if Collateral::<T>::iter_prefix(who).next().is_some() {
+            return;
+        }
+        let debts: Vec<AssetIdOf<T>> = Debts::<T>::iter_prefix(who).map(|(asset_id, _)| asset_id).collect();
+        for asset_id in debts {
+            let amount = Self::debt_balance(who, asset_id);
+            Debts::<T>::remove(who, asset_id);
+            Self::record_bad_debt(asset_id, amount);
+        }
+    }
+
+    /// `amount * numerator / denominator`, rounded down.
+    fn scale(amount: BalanceOf<T>, numerator: BalanceOf<T>, denominator: BalanceOf<T>) -> Result<BalanceOf<T>, Error<T>> {
+        multiply_by_rational(
+            amount.saturated_into::<u128>(),
+            numerator.saturated_into::<u128>(),
+            denominator.saturated_into::<u128>(),
+        )
+        .map_err(|_| Error::<T>::Overflow)?
+        .try_into()
+        .map_err(|_| Error::<T>::Overflow)
+    }
+
+    /// Close every auction whose price has reached the floor, returning the weight used.
+    fn settle_expired_auctions(now: T::BlockNumber) -> Weight {
+        let active = ActiveAuctions::<T>::get();
+        let mut weight = T::DbWeight::get().reads(1);
+        for auction_id in active {
+            weight = weight.saturating_add(T::DbWeight::get().reads(1));
+            if let Some(auction) = Auctions::<T>::get(auction_id) {
+                if now >= auction.end {
+                    weight = weight.saturating_add(T::DbWeight::get().reads_writes(3, 5));
+                    Self::close_auction(auction_id, auction);
+                }
+            }
+        }
+        weight
+    }
+
+    /// Remove `finder` from the set, returning whether it was registered.
+    fn remove_finder(finder: &T::AccountId) -> bool {
+        Finders::<T>::mutate(|finders| match finders.binary_search(finder) {
+            Ok(index) => {
+                finders.remove(index);
+                true
+            }
+            Err(_) => false,
+        })
+    }
+
+    /// Debt of `who` in an asset, including interest accrued up to the last vault accrual.
+    pub fn debt_balance(who: &T::AccountId, asset_id: AssetIdOf<T>) -> BalanceOf<T> {
+        let snapshot = Debts::<T>::get(who, asset_id);
+        if snapshot.principal.is_zero() || snapshot.interest_index.is_zero() {
+            return snapshot.principal;
+        }
+        // debt = principal * borrow_index / interest_index
+        let growth = <Vault::Pallet<T>>::borrow_index(asset_id) / snapshot.interest_index;
+        growth.saturating_mul_int(snapshot.principal.saturated_into::<u128>()).saturated_into()
+    }
+
+    /// Value of `amount` of an asset at the oracle price.
+    pub fn asset_value(asset_id: AssetIdOf<T>, amount: BalanceOf<T>) -> Result<FixedU128, Error<T>> {
+        T::PriceProvider::value(asset_id, amount).ok_or(Error::<T>::NoPrice)
+    }
+
+    /// Collateral seized for repaying `repay_amount` of debt, and the bonus included in it.
+    ///
+    /// The seized amount is worth the repaid debt at oracle prices plus the collateral bonus.
+    pub fn seize_amount(
+        pay_asset_id: AssetIdOf<T>,
+        get_asset_id: AssetIdOf<T>,
+        repay_amount: BalanceOf<T>,
+    ) -> Result<(BalanceOf<T>, BalanceOf<T>), Error<T>> {
+        let params = CollateralAssets::<T>::get(get_asset_id).ok_or(Error::<T>::NotCollateral)?;
+
+        let repay_value = Self::asset_value(pay_asset_id, repay_amount)?;
+        let base = T::PriceProvider::amount(get_asset_id, repay_value).ok_or(Error::<T>::NoPrice)?;
+        let bonus = params.liquidation_bonus * base;
+        let seized = base.checked_add(&bonus).ok_or(Error::<T>::Overflow)?;
+        Ok((seized, bonus))
+    }
+
+    /// Value of the collateral of `who`, each asset weighted by `weight` of its parameters.
+    fn weighted_collateral_value(
+        who: &T::AccountId,
+        weight: impl Fn(&CollateralParams) -> Permill,
+    ) -> Result<FixedU128, Error<T>> {
+        Collateral::<T>::iter_prefix(who).try_fold(FixedU128::zero(), |total, (asset_id, amount)| {
+            let params = CollateralAssets::<T>::get(asset_id).unwrap_or_default();
+            let value = Self::asset_value(asset_id, amount)?;
+            Ok(total.saturating_add(value.saturating_mul(weight(&params).into())))
+        })
+    }
+
+    /// Value of all the debt of `who`.
+    pub fn debt_value(who: &T::AccountId) -> Result<FixedU128, Error<T>> {
+        Debts::<T>::iter_prefix(who).try_fold(FixedU128::zero(), |total, (asset_id, _)| {
+            Ok(total.saturating_add(Self::asset_value(asset_id, Self::debt_balance(who, asset_id))?))
+        })
+    }
+
+    /// Collateral value at the liquidation thresholds over debt value.
+    ///
+    /// A position can be liquidated once this drops below one. Accounts without debt
+    /// have the maximum health factor, positions priced by a stale oracle have none.
+    pub fn health_factor(who: &T::AccountId) -> Result<FixedU128, Error<T>> {
+        let debt = Self::debt_value(who)?;
+        if debt.is_zero() {
+            return Ok(FixedU128::max_value());
+        }
+        let collateral = Self::weighted_collateral_value(who, |params| params.liquidation_threshold)?;
+        Ok(collateral.checked_div(&debt).unwrap_or_else(FixedU128::max_value))
+    }
+
+    /// Ensure the debt of `who` is covered by its collateral at the collateral factors.
+    fn ensure_collateralized(who: &T::AccountId) -> DispatchResult {
+        let limit = Self::weighted_collateral_value(who, |params| params.collateral_factor)?;
+        ensure!(Self::debt_value(who)? <= limit, Error::<T>::Undercollateralized);
+        Ok(())
+    }
+
+    fn set_collateral(who: &T::AccountId, asset_id: AssetIdOf<T>, amount: BalanceOf<T>) {
+        if amount.is_zero() {
+            Collateral::<T>::remove(who, asset_id);
+        } else {
+            Collateral::<T>::insert(who, asset_id, amount);
+        }
+    }
+
+    fn set_debt(who: &T::AccountId, asset_id: AssetIdOf<T>, amount: BalanceOf<T>) {
+        if amount.is_zero() {
+            Debts::<T>::remove(who, asset_id);
+        } else {
+            Debts::<T>::insert(who, asset_id, Vault::BorrowSnapshot {
+                principal: amount,
+                interest_index: <Vault::Pallet<T>>::borrow_index(asset_id),
+            });
+        }
     }
 }
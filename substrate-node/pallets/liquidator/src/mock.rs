@@ -1,14 +1,33 @@
-use crate as pallet_template;
+use crate as pallet_liquidator;
 use sp_core::H256;
-use frame_support::parameter_types;
+use frame_support::{dispatch::DispatchResult, parameter_types, PalletId, weights::Weight};
 use sp_runtime::{
-	traits::{BlakeTwo256, IdentityLookup}, testing::Header,
+	traits::{AccountIdConversion, BlakeTwo256, Extrinsic as ExtrinsicT, IdentityLookup},
+	testing::{Header, TestSignature, UintAuthorityId}, DispatchError, FixedPointNumber, FixedU128, Permill,
 };
 use frame_system as system;
+use frame_system::offchain::{AppCrypto, CreateSignedTransaction, SendTransactionTypes, SigningTypes};
+use pallet_currencies::MultiCurrency;
 
-type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+pub type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
 type Block = frame_system::mocking::MockBlock<Test>;
 
+pub const ALICE: u64 = 1;
+pub const BOB: u64 = 2;
+pub const CHARLIE: u64 = 3;
+/// Counterparty of the mock swap market.
+pub const MARKET: u64 = 4;
+
+/// Asset lent by the vault.
+pub const DOT: u32 = 0;
+/// rToken of `DOT`.
+pub const RDOT: u32 = 1;
+/// Asset accepted as collateral.
+pub const KSM: u32 = 2;
+
+/// Balances of the assets, held in storage without a backing pallet.
+pub type Currencies = pallet_currencies::InMemoryCurrencies<u32, u64>;
+
 // Configure a mock runtime to test the pallet.
 frame_support::construct_runtime!(
 	pub enum Test where
@@ -17,7 +36,10 @@ frame_support::construct_runtime!(
 		UncheckedExtrinsic = UncheckedExtrinsic,
 	{
 		System: frame_system::{Module, Call, Config, Storage, Event<T>},
-		TemplateModule: pallet_template::{Module, Call, Storage, Event<T>},
+		Balances: pallet_balances::{Module, Call, Storage, Config<T>, Event<T>},
+		Vault: pallet_vault::{Module, Call, Storage, Config<T>, Event<T>},
+		LiquidatorAdapter: pallet_liquidator_adapter::{Module, Call, Storage, Config<T>, Event<T>},
+		Liquidator: pallet_liquidator::{Module, Call, Storage, Config<T>, Event<T>},
 	}
 );
 
@@ -44,18 +66,235 @@ impl system::Config for Test {
 	type BlockHashCount = BlockHashCount;
 	type Version = ();
 	type PalletInfo = PalletInfo;
-	type AccountData = ();
+	type AccountData = pallet_balances::AccountData<u64>;
 	type OnNewAccount = ();
 	type OnKilledAccount = ();
 	type SystemWeightInfo = ();
 	type SS58Prefix = SS58Prefix;
 }
 
-impl pallet_template::Config for Test {
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 1;
+	pub const MaxLocks: u32 = 50;
+}
+
+impl pallet_balances::Config for Test {
+	type MaxLocks = MaxLocks;
+	type Balance = u64;
+	type Event = Event;
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const VaultPalletId: PalletId = PalletId(*b"rp/vault");
+	pub const LiquidatorPalletId: PalletId = PalletId(*b"rp/liqtr");
+	pub const FlashLoanFee: Permill = Permill::from_percent(1);
+	pub const MaxWithdrawalRequests: u32 = 4;
+	pub const WithdrawalQueueWeight: Weight = 1_000_000_000;
+}
+
+/// Creates rTokens in `InMemoryCurrencies`, where every asset already exists.
+pub struct MockRTokenFactory;
+
+impl pallet_vault::RTokenFactory<u64, u32, u64> for MockRTokenFactory {
+	fn create(_: u32, _: &u64, _: u64) -> DispatchResult {
+		Ok(())
+	}
+
+	fn metadata(_: u32) -> (Vec<u8>, Vec<u8>, u8) {
+		(Vec::new(), Vec::new(), 0)
+	}
+
+	fn set_metadata(_: u32, _: &u64, _: Vec<u8>, _: Vec<u8>, _: u8) -> DispatchResult {
+		Ok(())
+	}
+
+	fn owner(_: u32) -> Option<u64> {
+		Some(VaultPalletId::get().into_account())
+	}
+}
+
+impl pallet_vault::Config for Test {
+	type Event = Event;
+	type Balance = u64;
+	type AssetId = u32;
+	type PalletId = VaultPalletId;
+	type LiquidatorPalletId = LiquidatorPalletId;
+	type Currencies = Currencies;
+	type RegisterOrigin = frame_system::EnsureRoot<u64>;
+	type RTokenFactory = MockRTokenFactory;
+	type AdminOrigin = frame_system::EnsureRoot<u64>;
+	type Call = Call;
+	type FlashLoanFee = FlashLoanFee;
+	type MaxWithdrawalRequests = MaxWithdrawalRequests;
+	type WithdrawalQueueWeight = WithdrawalQueueWeight;
+}
+
+parameter_types! {
+	pub static SwapPercent: u64 = 100;
+	pub static StalePrices: bool = false;
+	pub static PausedAsset: Option<u32> = None;
+	pub static KsmPrice: FixedU128 = FixedU128::saturating_from_integer(2);
+}
+
+/// Prices KSM at `KsmPrice` and every other asset at 2 per smallest unit, or everything at
+/// nothing while `StalePrices` is set, and pauses `PausedAsset`.
+pub struct MockPriceProvider;
+
+impl pallet_oracle::PriceProvider<u32, u64> for MockPriceProvider {
+	fn price(asset_id: u32) -> Option<FixedU128> {
+		if StalePrices::get() {
+			return None;
+		}
+		Some(if asset_id == KSM { KsmPrice::get() } else { FixedU128::saturating_from_integer(2) })
+	}
+
+	fn value(asset_id: u32, amount: u64) -> Option<FixedU128> {
+		Self::price(asset_id)?.checked_mul(&FixedU128::saturating_from_integer(amount))
+	}
+
+	fn amount(asset_id: u32, value: FixedU128) -> Option<u64> {
+		let units = value.checked_div(&Self::price(asset_id)?)?;
+		Some((units.into_inner() / FixedU128::accuracy()) as u64)
+	}
+
+	fn twap(asset_id: u32) -> Option<FixedU128> {
+		Self::price(asset_id)
+	}
+
+	fn is_paused(asset_id: u32) -> bool {
+		PausedAsset::get() == Some(asset_id)
+	}
+}
+
+/// Swaps with `MARKET` at `SwapPercent` of equal prices.
+pub struct MockSwap;
+
+impl pallet_liquidator_adapter::Swap<u64, u32, u64> for MockSwap {
+	fn quote_swap(_: u32, _: u32, amount_in: u64) -> Result<u64, DispatchError> {
+		Ok(amount_in * SwapPercent::get() / 100)
+	}
+
+	fn swap(who: &u64, asset_in: u32, asset_out: u32, amount_in: u64, _: u64) -> Result<u64, DispatchError> {
+		let amount_out = Self::quote_swap(asset_in, asset_out, amount_in)?;
+		Currencies::transfer(asset_in, who, &MARKET, amount_in)?;
+		Currencies::transfer(asset_out, &MARKET, who, amount_out)?;
+		Ok(amount_out)
+	}
+}
+
+impl pallet_liquidator_adapter::Config for Test {
 	type Event = Event;
+	type AssetId = u32;
+	type Balance = u64;
+	type Currencies = Currencies;
+	type LiquidatorPalletId = LiquidatorPalletId;
+	type AdminOrigin = frame_system::EnsureRoot<u64>;
+	type Swap = MockSwap;
+}
+
+parameter_types! {
+	pub const FinderBond: u64 = 100;
+	pub const MaxFinders: u32 = 2;
+	pub const FinderRewardShare: Permill = Permill::from_percent(50);
+	pub const AuctionStartPremium: Permill = Permill::from_percent(20);
+	pub const AuctionFloor: Permill = Permill::from_percent(50);
+	pub const AuctionDuration: u64 = 10;
+	pub const MaxAuctions: u32 = 2;
+	pub const MaxBatchLiquidations: u32 = 2;
+	pub const MaxHistory: u32 = 3;
+	pub const MaxHistoryPerAccount: u32 = 2;
+	pub const KeeperInterval: u64 = 5;
+	pub const MaxKeeperLiquidations: u32 = 2;
+	pub static MaxKeeperScan: u32 = 2;
+}
+
+/// Signs keeper transactions with the keys set by `UintAuthorityId::set_all_keys`.
+pub struct TestAuthId;
+
+impl AppCrypto<UintAuthorityId, TestSignature> for TestAuthId {
+	type RuntimeAppPublic = UintAuthorityId;
+	type GenericSignature = TestSignature;
+	type GenericPublic = UintAuthorityId;
+}
+
+impl SigningTypes for Test {
+	type Public = UintAuthorityId;
+	type Signature = TestSignature;
+}
+
+impl<C> SendTransactionTypes<C> for Test where Call: From<C> {
+	type OverarchingCall = Call;
+	type Extrinsic = UncheckedExtrinsic;
+}
+
+impl<C> CreateSignedTransaction<C> for Test where Call: From<C> {
+	fn create_transaction<A: AppCrypto<Self::Public, Self::Signature>>(
+		call: Call,
+		_public: UintAuthorityId,
+		account: u64,
+		_nonce: u64,
+	) -> Option<(Call, <UncheckedExtrinsic as ExtrinsicT>::SignaturePayload)> {
+		Some((call, (account, (), ())))
+	}
+}
+
+impl pallet_liquidator::Config for Test {
+	type Event = Event;
+	type LiquidatorPalletId = LiquidatorPalletId;
+	type AdminOrigin = frame_system::EnsureRoot<u64>;
+	type BondCurrency = Balances;
+	type FinderBond = FinderBond;
+	type MaxFinders = MaxFinders;
+	type FinderRewardShare = FinderRewardShare;
+	type Slash = ();
+	type AuctionStartPremium = AuctionStartPremium;
+	type AuctionFloor = AuctionFloor;
+	type AuctionDuration = AuctionDuration;
+	type MaxAuctions = MaxAuctions;
+	type MaxBatchLiquidations = MaxBatchLiquidations;
+	type MaxHistory = MaxHistory;
+	type MaxHistoryPerAccount = MaxHistoryPerAccount;
+	type AuthorityId = TestAuthId;
+	type KeeperInterval = KeeperInterval;
+	type MaxKeeperLiquidations = MaxKeeperLiquidations;
+	type MaxKeeperScan = MaxKeeperScan;
+	type PriceProvider = MockPriceProvider;
+}
+
+pub fn liquidator_account() -> u64 {
+	LiquidatorPalletId::get().into_account()
 }
 
 // Build genesis storage according to the mock runtime.
 pub fn new_test_ext() -> sp_io::TestExternalities {
-	system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
+	let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(ALICE, 1_000), (BOB, 1_000), (CHARLIE, 1_000), (MARKET, 1_000), (liquidator_account(), 1_000)],
+	}.assimilate_storage(&mut t).unwrap();
+	pallet_vault::GenesisConfig::<Test> {
+		rtokens: vec![(DOT, RDOT, 1)],
+	}.assimilate_storage(&mut t).unwrap();
+	// Liquidations hold the collateral unless a test swaps it
+	pallet_liquidator_adapter::GenesisConfig::<Test> {
+		strategies: vec![(DOT, KSM, pallet_liquidator_adapter::StrategyKind::HoldForSale)],
+	}.assimilate_storage(&mut t).unwrap();
+	pallet_liquidator::GenesisConfig::<Test> {
+		finders: vec![CHARLIE],
+		finders_only: false,
+	}.assimilate_storage(&mut t).unwrap();
+
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| {
+		System::set_block_number(1);
+		Currencies::mint(DOT, &ALICE, 1_000).unwrap();
+		Currencies::mint(KSM, &BOB, 1_000).unwrap();
+		Currencies::mint(DOT, &MARKET, 1_000).unwrap();
+		// ALICE backs the vault so positions have something to borrow
+		Vault::vault_deposit(Origin::signed(ALICE), DOT, 1_000).unwrap();
+	});
+	ext
 }
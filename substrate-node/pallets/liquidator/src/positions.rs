@@ -0,0 +1,35 @@
+use codec::{Decode, Encode};
+use sp_runtime::{traits::Saturating, PerThing, Permill, RuntimeDebug};
+
+/// Risk parameters of an asset accepted as collateral.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, Default, RuntimeDebug)]
+pub struct CollateralParams {
+    /// Share of the collateral value that can be borrowed against
+    pub collateral_factor: Permill,
+    /// Share of the collateral value below which debt becomes liquidatable
+    pub liquidation_threshold: Permill,
+    /// Extra collateral seized on top of the repaid debt value
+    pub liquidation_bonus: Permill,
+}
+
+impl CollateralParams {
+    /// Positions must become liquidatable no earlier than they stop being able to borrow,
+    /// and seizing collateral with the bonus must not leave them less healthy.
+    pub fn is_valid(&self) -> bool {
+        let seized_share = self.liquidation_threshold.saturating_add(self.liquidation_bonus * self.liquidation_threshold);
+        self.collateral_factor <= self.liquidation_threshold && seized_share < Permill::one()
+    }
+}
+
+/// A single liquidation of a batch.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Default, RuntimeDebug)]
+pub struct LiquidationOrder<AccountId, AssetId, Balance> {
+    /// The position being liquidated
+    pub target: AccountId,
+    /// The debt asset being repaid
+    pub pay_asset_id: AssetId,
+    /// The collateral asset being seized
+    pub get_asset_id: AssetId,
+    /// The debt to repay, at most the close factor of the target's debt
+    pub repay_amount: Balance,
+}
@@ -1,23 +1,505 @@
-use crate::{Error, mock::*};
-use frame_support::{assert_ok, assert_noop};
+use crate::{CollateralParams, Error, LiquidationOrder, LiquidationRecord, mock::*};
+use codec::Decode;
+use pallet_currencies::MultiCurrency;
+use pallet_liquidator_adapter::StrategyKind;
+use frame_support::{assert_ok, assert_noop, traits::{OffchainWorker, OnInitialize, ReservableCurrency}};
+use sp_core::offchain::{testing, OffchainDbExt, OffchainWorkerExt, TransactionPoolExt};
+use sp_runtime::{testing::UintAuthorityId, traits::Bounded, DispatchError, FixedPointNumber, FixedU128, PerThing, Permill};
+
+fn params(collateral_factor: u32, liquidation_threshold: u32) -> CollateralParams {
+	CollateralParams {
+		collateral_factor: Permill::from_percent(collateral_factor),
+		liquidation_threshold: Permill::from_percent(liquidation_threshold),
+		liquidation_bonus: Permill::from_percent(10),
+	}
+}
+
+/// BOB posts 500 KSM at a 50% collateral factor and borrows 200 DOT.
+fn open_position() {
+	assert_ok!(Liquidator::set_collateral_params(Origin::root(), KSM, params(50, 80)));
+	assert_ok!(Liquidator::deposit_collateral(Origin::signed(BOB), KSM, 500));
+	assert_ok!(Liquidator::borrow(Origin::signed(BOB), DOT, 200));
+}
+
+#[test]
+fn collateral_params_must_be_valid() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Liquidator::set_collateral_params(Origin::root(), KSM, params(90, 80)),
+			Error::<Test>::InvalidCollateralParams
+		);
+		assert_noop!(
+			Liquidator::deposit_collateral(Origin::signed(BOB), KSM, 500),
+			Error::<Test>::NotCollateral
+		);
+	});
+}
+
+#[test]
+fn borrow_is_limited_by_collateral_factor() {
+	new_test_ext().execute_with(|| {
+		open_position();
+		assert_eq!(Currencies::free_balance(DOT, &BOB), 200);
+		assert_eq!(Liquidator::debt_balance(&BOB, DOT), 200);
+		assert_eq!(Vault::borrow_balance(DOT, &liquidator_account()), 200);
+
+		assert_noop!(Liquidator::borrow(Origin::signed(BOB), DOT, 51), Error::<Test>::Undercollateralized);
+		assert_noop!(
+			Liquidator::withdraw_collateral(Origin::signed(BOB), KSM, 101),
+			Error::<Test>::Undercollateralized
+		);
+		assert_ok!(Liquidator::withdraw_collateral(Origin::signed(BOB), KSM, 100));
+	});
+}
+
+#[test]
+fn health_factor_uses_liquidation_threshold() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(Liquidator::health_factor(&BOB), Ok(FixedU128::max_value()));
+		open_position();
+		// 500 KSM * 80% over 200 DOT at equal prices
+		assert_eq!(Liquidator::health_factor(&BOB), Ok(FixedU128::saturating_from_integer(2)));
+
+		assert_ok!(Liquidator::repay(Origin::signed(BOB), DOT, 200));
+		assert_eq!(Liquidator::debt_balance(&BOB, DOT), 0);
+		assert_eq!(Vault::borrow_balance(DOT, &liquidator_account()), 0);
+		assert_eq!(Liquidator::health_factor(&BOB), Ok(FixedU128::max_value()));
+	});
+}
+
+#[test]
+fn liquidate_requires_unhealthy_target() {
+	new_test_ext().execute_with(|| {
+		open_position();
+		assert_noop!(
+			Liquidator::liquidate(Origin::signed(CHARLIE), BOB, DOT, KSM, 100),
+			Error::<Test>::PositionHealthy
+		);
+		assert_noop!(
+			Liquidator::liquidate(Origin::signed(CHARLIE), ALICE, DOT, KSM, 100),
+			Error::<Test>::NoDebt
+		);
+
+		// Governance tightens KSM, leaving BOB at a quarter of the required collateral
+		assert_ok!(Liquidator::set_collateral_params(Origin::root(), KSM, params(10, 10)));
+
+		// Positions can't be judged without fresh oracle prices
+		StalePrices::set(true);
+		assert_noop!(Liquidator::liquidate(Origin::signed(CHARLIE), BOB, DOT, KSM, 100), Error::<Test>::NoPrice);
+		assert_noop!(Liquidator::borrow(Origin::signed(BOB), DOT, 1), Error::<Test>::NoPrice);
+		assert!(Liquidator::keeper_orders(None).0.is_empty());
+		StalePrices::set(false);
+
+		// Nor while the oracle circuit breaker of one of its assets is tripped
+		PausedAsset::set(Some(KSM));
+		assert_noop!(
+			Liquidator::liquidate(Origin::signed(CHARLIE), BOB, DOT, KSM, 100),
+			Error::<Test>::LiquidationsPaused
+		);
+		assert!(Liquidator::keeper_orders(None).0.is_empty());
+		PausedAsset::set(None);
+
+		assert_ok!(Liquidator::liquidate(Origin::signed(CHARLIE), BOB, DOT, KSM, 100));
+	});
+}
+
+#[test]
+fn liquidate_is_bounded_by_close_factor_and_seizes_bonus() {
+	new_test_ext().execute_with(|| {
+		open_position();
+		assert_ok!(Liquidator::set_collateral_params(Origin::root(), KSM, params(10, 10)));
+
+		// Half of the 200 DOT debt at the default close factor
+		assert_noop!(
+			Liquidator::liquidate(Origin::signed(CHARLIE), BOB, DOT, KSM, 101),
+			Error::<Test>::ExceedCloseFactor
+		);
+		assert_ok!(Liquidator::liquidate(Origin::signed(CHARLIE), BOB, DOT, KSM, 100));
+
+		// 100 DOT worth of KSM at equal prices plus the 10% bonus
+		assert_eq!(Liquidator::debt_balance(&BOB, DOT), 100);
+		assert_eq!(Liquidator::collateral(BOB, KSM), 390);
+		assert_eq!(Liquidator::seized_collateral(KSM), 105);
+		assert_eq!(Liquidator::held_debt(KSM, DOT), 100);
+		assert!(System::events().iter().any(|record| record.event ==
+			Event::pallet_liquidator(crate::Event::Liquidated(CHARLIE, BOB, DOT, 100, KSM, 110, 10))));
+	});
+}
+
+fn order(target: u64, repay_amount: u64) -> LiquidationOrder<u64, u32, u64> {
+	LiquidationOrder { target, pay_asset_id: DOT, get_asset_id: KSM, repay_amount }
+}
+
+#[test]
+fn batch_liquidations_are_best_effort_or_all_or_nothing() {
+	new_test_ext().execute_with(|| {
+		open_position();
+		assert_ok!(Liquidator::set_collateral_params(Origin::root(), KSM, params(10, 10)));
+		let orders = vec![order(BOB, 50), order(ALICE, 50)];
+
+		assert_noop!(
+			Liquidator::liquidate_batch(Origin::signed(CHARLIE), vec![order(BOB, 10); 3], false),
+			Error::<Test>::TooManyOrders
+		);
+		assert_noop!(
+			Liquidator::liquidate_batch(Origin::signed(CHARLIE), orders.clone(), true),
+			Error::<Test>::NoDebt
+		);
+
+		assert_ok!(Liquidator::liquidate_batch(Origin::signed(CHARLIE), orders, false));
+		assert_eq!(Liquidator::debt_balance(&BOB, DOT), 150);
+		let events: Vec<_> = System::events().into_iter().map(|record| record.event).collect();
+		assert!(events.contains(&Event::pallet_liquidator(crate::Event::BatchOrderFailed(
+			1, ALICE, Error::<Test>::NoDebt.into()
+		))));
+		assert!(events.contains(&Event::pallet_liquidator(crate::Event::BatchLiquidated(CHARLIE, 1))));
+	});
+}
+
+#[test]
+fn liquidation_history_is_paged_and_pruned() {
+	new_test_ext().execute_with(|| {
+		open_position();
+		assert_ok!(Liquidator::set_collateral_params(Origin::root(), KSM, params(10, 10)));
+		for _ in 0..4 {
+			assert_ok!(Liquidator::liquidate(Origin::signed(CHARLIE), BOB, DOT, KSM, 10));
+		}
+
+		// Only the latest three records are kept and two are indexed per account
+		assert!(Liquidator::liquidation_record(0).is_none());
+		assert!(Liquidator::liquidation_record(1).is_some());
+		assert_eq!(Liquidator::target_history_ids(BOB), vec![2, 3]);
+		assert_eq!(Liquidator::finder_history_ids(CHARLIE), vec![2, 3]);
+
+		let record = LiquidationRecord {
+			block: 1,
+			target: BOB,
+			finder: CHARLIE,
+			pay_asset_id: DOT,
+			repaid: 10,
+			get_asset_id: KSM,
+			seized: 11,
+			bonus: 1,
+			pay_price: FixedU128::saturating_from_integer(2),
+			get_price: FixedU128::saturating_from_integer(2),
+		};
+		assert_eq!(Liquidator::target_history(&BOB, 0, 10), vec![record.clone(), record.clone()]);
+		assert_eq!(Liquidator::finder_history(&CHARLIE, 1, 10), vec![record]);
+		assert!(Liquidator::target_history(&CHARLIE, 0, 10).is_empty());
+	});
+}
+
+#[test]
+fn direct_swap_strategy_repays_the_loan() {
+	new_test_ext().execute_with(|| {
+		open_position();
+		assert_ok!(Liquidator::set_collateral_params(Origin::root(), KSM, params(10, 10)));
+		assert_ok!(LiquidatorAdapter::set_strategy(Origin::root(), DOT, KSM, StrategyKind::DirectSwap));
+		assert_eq!(LiquidatorAdapter::quote(DOT, KSM, 105), Ok(105));
+
+		// The 105 KSM left after the finder reward must raise the 100 DOT repaid
+		SwapPercent::set(50);
+		assert_noop!(
+			Liquidator::liquidate(Origin::signed(CHARLIE), BOB, DOT, KSM, 100),
+			pallet_liquidator_adapter::Error::<Test>::InsufficientProceeds
+		);
+
+		SwapPercent::set(100);
+		assert_ok!(Liquidator::liquidate(Origin::signed(CHARLIE), BOB, DOT, KSM, 100));
+		assert_eq!(Liquidator::seized_collateral(KSM), 0);
+		assert_eq!(Currencies::free_balance(KSM, &MARKET), 105);
+		assert_eq!(Vault::borrow_balance(DOT, &liquidator_account()), 100);
+		assert_eq!(Currencies::free_balance(DOT, &liquidator_account()), 5);
+	});
+}
+
+#[test]
+fn held_collateral_is_sold_by_governance_to_repay_the_vault() {
+	new_test_ext().execute_with(|| {
+		open_position();
+		assert_ok!(Liquidator::set_collateral_params(Origin::root(), KSM, params(10, 10)));
+		assert_ok!(Liquidator::liquidate(Origin::signed(CHARLIE), BOB, DOT, KSM, 100));
+		assert_eq!(Liquidator::seized_collateral(KSM), 105);
+		assert_eq!(Liquidator::held_debt(KSM, DOT), 100);
+		assert_eq!(Vault::borrow_balance(DOT, &liquidator_account()), 200);
+
+		assert_noop!(
+			Liquidator::sell_seized_collateral(Origin::signed(CHARLIE), KSM, DOT, 105, 0),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Liquidator::sell_seized_collateral(Origin::root(), KSM, DOT, 106, 0),
+			Error::<Test>::ExceedCollateral
+		);
+
+		// The proceeds repay the held debt and the surplus stays with the liquidator
+		assert_ok!(Liquidator::sell_seized_collateral(Origin::root(), KSM, DOT, 105, 105));
+		assert_eq!(Liquidator::seized_collateral(KSM), 0);
+		assert_eq!(Liquidator::held_debt(KSM, DOT), 0);
+		assert_eq!(Vault::borrow_balance(DOT, &liquidator_account()), 100);
+		assert_eq!(Currencies::free_balance(DOT, &liquidator_account()), 5);
+		assert!(System::events().iter().any(|record| record.event ==
+			Event::pallet_liquidator(crate::Event::SeizedCollateralSold(KSM, 105, DOT, 105, 100))));
+	});
+}
+
+#[test]
+fn liquidations_run_inside_a_flash_loan() {
+	new_test_ext().execute_with(|| {
+		open_position();
+		assert_ok!(Liquidator::set_collateral_params(Origin::root(), KSM, params(10, 10)));
+		assert_ok!(LiquidatorAdapter::set_strategy(Origin::root(), DOT, KSM, StrategyKind::DirectSwap));
+
+		// The liquidation repays the vault from within the loan, which still has to be returned
+		let liquidate = Box::new(Call::Liquidator(crate::Call::liquidate(BOB, DOT, KSM, 100)));
+		assert_noop!(
+			Vault::flash_loan(Origin::signed(CHARLIE), DOT, 100, liquidate),
+			pallet_vault::Error::<Test>::FlashLoanNotRepaid
+		);
+	});
+}
 
 #[test]
-fn it_works_for_default_value() {
+fn close_factor_is_set_by_governance() {
 	new_test_ext().execute_with(|| {
-		// Dispatch a signed extrinsic.
-		assert_ok!(TemplateModule::do_something(Origin::signed(1), 42));
-		// Read pallet storage and assert an expected result.
-		assert_eq!(TemplateModule::something(), Some(42));
+		assert_eq!(Liquidator::close_factor(), Permill::from_percent(50));
+		assert_noop!(
+			Liquidator::set_close_factor(Origin::root(), Permill::zero()),
+			Error::<Test>::InvalidCloseFactor
+		);
+		assert_ok!(Liquidator::set_close_factor(Origin::root(), Permill::one()));
+		assert_eq!(Liquidator::close_factor(), Permill::one());
 	});
 }
 
 #[test]
-fn correct_error_for_none_value() {
+fn finders_register_with_a_bond_in_sorted_order() {
 	new_test_ext().execute_with(|| {
-		// Ensure the expected error is thrown when no value is present.
+		assert_ok!(Liquidator::register_finder(Origin::signed(ALICE)));
+		assert_eq!(Liquidator::finders(), vec![ALICE, CHARLIE]);
+		assert_eq!(Balances::reserved_balance(ALICE), 100);
+		assert_noop!(Liquidator::register_finder(Origin::signed(ALICE)), Error::<Test>::AlreadyFinder);
+		assert_noop!(Liquidator::register_finder(Origin::signed(BOB)), Error::<Test>::TooManyFinders);
+
+		assert_ok!(Liquidator::unregister_finder(Origin::signed(ALICE)));
+		assert_eq!(Liquidator::finders(), vec![CHARLIE]);
+		assert_eq!(Balances::reserved_balance(ALICE), 0);
+	});
+}
+
+#[test]
+fn finders_only_mode_restricts_liquidations_and_pays_rewards() {
+	new_test_ext().execute_with(|| {
+		open_position();
+		assert_ok!(Liquidator::set_collateral_params(Origin::root(), KSM, params(10, 10)));
+		assert_ok!(Liquidator::set_finders_only(Origin::root(), true));
+
 		assert_noop!(
-			TemplateModule::cause_error(Origin::signed(1)),
-			Error::<Test>::NoneValue
+			Liquidator::liquidate(Origin::signed(ALICE), BOB, DOT, KSM, 100),
+			Error::<Test>::NotFinder
 		);
+		assert_ok!(Liquidator::liquidate(Origin::signed(CHARLIE), BOB, DOT, KSM, 100));
+		// Half of the 10 KSM bonus goes to the finder
+		assert_eq!(Currencies::free_balance(KSM, &CHARLIE), 5);
+		assert_eq!(Liquidator::seized_collateral(KSM), 105);
+	});
+}
+
+#[test]
+fn slashed_finders_below_the_bond_are_removed() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Liquidator::register_finder(Origin::signed(ALICE)));
+		assert_ok!(Liquidator::slash_finder(Origin::root(), ALICE, 40));
+
+		assert_eq!(Balances::free_balance(ALICE), 960);
+		assert_eq!(Balances::reserved_balance(ALICE), 0);
+		assert!(!Liquidator::is_finder(&ALICE));
+		assert_noop!(Liquidator::slash_finder(Origin::root(), ALICE, 40), Error::<Test>::NotFinder);
+	});
+}
+
+#[test]
+fn finders_are_slashed_no_more_than_their_reserve() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Liquidator::register_finder(Origin::signed(ALICE)));
+		Balances::unreserve(&ALICE, 70);
+
+		assert_ok!(Liquidator::slash_finder(Origin::root(), ALICE, 40));
+		assert!(System::events().iter().any(|record| record.event ==
+			Event::pallet_liquidator(crate::Event::FinderSlashed(ALICE, 30))));
+		assert_eq!(Balances::free_balance(ALICE), 970);
+		assert_eq!(Balances::reserved_balance(ALICE), 0);
+		assert!(!Liquidator::is_finder(&ALICE));
+	});
+}
+
+/// Liquidates half of BOB's debt into an auction of 105 KSM for 100 DOT.
+fn start_auction() {
+	open_position();
+	assert_ok!(Liquidator::set_collateral_params(Origin::root(), KSM, params(10, 10)));
+	assert_ok!(Liquidator::set_auction_threshold(Origin::root(), DOT, Some(50)));
+	assert_ok!(Liquidator::liquidate(Origin::signed(CHARLIE), BOB, DOT, KSM, 100));
+}
+
+#[test]
+fn large_liquidations_are_sold_by_decaying_auction() {
+	new_test_ext().execute_with(|| {
+		start_auction();
+		assert_eq!(Liquidator::active_auctions(), vec![0]);
+		assert_eq!(Liquidator::seized_collateral(KSM), 0);
+		assert_ok!(Currencies::mint(DOT, &CHARLIE, 500));
+
+		// Starts at the oracle price plus the 20% premium
+		assert_ok!(Liquidator::bid(Origin::signed(CHARLIE), 0, 50));
+		assert_eq!(Currencies::free_balance(DOT, &CHARLIE), 440);
+
+		// Halfway to the 50% floor the remaining 40 DOT buys 47 KSM
+		System::set_block_number(6);
+		assert_ok!(Liquidator::bid(Origin::signed(CHARLIE), 0, 100));
+		assert_eq!(Currencies::free_balance(DOT, &CHARLIE), 400);
+		assert_eq!(Currencies::free_balance(KSM, &CHARLIE), 102);
+
+		// The debt is raised, so the rest of the lot goes back to BOB
+		assert!(Liquidator::auctions(0).is_none());
+		assert_eq!(Liquidator::collateral(BOB, KSM), 398);
+		assert_eq!(Vault::borrow_balance(DOT, &liquidator_account()), 100);
+		assert_noop!(Liquidator::bid(Origin::signed(CHARLIE), 0, 1), Error::<Test>::UnknownAuction);
+	});
+}
+
+#[test]
+fn bids_pay_no_more_than_the_vault_is_owed() {
+	new_test_ext().execute_with(|| {
+		start_auction();
+		assert_ok!(Currencies::mint(DOT, &CHARLIE, 500));
+		Vault::record_bad_debt(&liquidator_account(), DOT, 150);
+		assert_eq!(Vault::borrow_balance(DOT, &liquidator_account()), 50);
+
+		// The whole lot is offered, but only the 50 DOT still owed are paid
+		assert_ok!(Liquidator::bid(Origin::signed(CHARLIE), 0, 105));
+		assert_eq!(Currencies::free_balance(DOT, &CHARLIE), 450);
+		assert_eq!(Currencies::free_balance(DOT, &liquidator_account()), 0);
+		assert_eq!(Vault::borrow_balance(DOT, &liquidator_account()), 0);
+		assert!(Liquidator::auctions(0).is_none());
+	});
+}
+
+#[test]
+fn expired_auctions_hold_the_lot_against_the_debt() {
+	new_test_ext().execute_with(|| {
+		start_auction();
+
+		Liquidator::on_initialize(10);
+		assert_eq!(Liquidator::active_auctions(), vec![0]);
+
+		Liquidator::on_initialize(11);
+		assert!(Liquidator::active_auctions().is_empty());
+		assert_eq!(Liquidator::seized_collateral(KSM), 105);
+		assert_eq!(Liquidator::held_debt(KSM, DOT), 100);
+		assert_eq!(Liquidator::bad_debt(DOT), 0);
+
+		// Selling the lot repays the vault before anything is written off
+		assert_ok!(Liquidator::sell_seized_collateral(Origin::root(), KSM, DOT, 105, 0));
+		assert_eq!(Liquidator::held_debt(KSM, DOT), 0);
+		assert_eq!(Vault::borrow_balance(DOT, &liquidator_account()), 100);
+		assert_eq!(Vault::bad_debt(DOT), 0);
+	});
+}
+
+#[test]
+fn debt_left_after_selling_all_held_collateral_is_bad_debt() {
+	new_test_ext().execute_with(|| {
+		start_auction();
+		Liquidator::on_initialize(11);
+
+		// At half price the 105 KSM lot only raises 52 of the 100 DOT owed
+		SwapPercent::set(50);
+		assert_ok!(Liquidator::sell_seized_collateral(Origin::root(), KSM, DOT, 100, 0));
+		assert_eq!(Liquidator::held_debt(KSM, DOT), 50);
+		assert_eq!(Liquidator::bad_debt(DOT), 0);
+
+		assert_ok!(Liquidator::sell_seized_collateral(Origin::root(), KSM, DOT, 5, 0));
+		assert_eq!(Liquidator::held_debt(KSM, DOT), 0);
+		assert_eq!(Liquidator::bad_debt(DOT), 48);
+		assert_eq!(Vault::bad_debt(DOT), 48);
+		assert_eq!(Vault::borrow_balance(DOT, &liquidator_account()), 100);
+	});
+}
+
+#[test]
+fn insolvent_positions_are_written_off() {
+	new_test_ext().execute_with(|| {
+		open_position();
+		assert_ok!(Liquidator::set_close_factor(Origin::root(), Permill::one()));
+
+		// KSM falls to a fifth of DOT, so the 500 KSM only cover 90 DOT of debt with the bonus
+		KsmPrice::set(FixedU128::saturating_from_rational(4, 10));
+		assert_ok!(Liquidator::liquidate(Origin::signed(CHARLIE), BOB, DOT, KSM, 200));
+		assert_eq!(Liquidator::collateral(BOB, KSM), 0);
+		assert_eq!(Liquidator::debt_balance(&BOB, DOT), 0);
+		assert_eq!(Liquidator::bad_debt(DOT), 110);
+		assert_eq!(Vault::bad_debt(DOT), 110);
+		assert_eq!(Vault::borrow_balance(DOT, &liquidator_account()), 90);
+
+		// Selling the held collateral repays the rest
+		assert_ok!(Liquidator::sell_seized_collateral(Origin::root(), KSM, DOT, Liquidator::seized_collateral(KSM), 0));
+		assert_eq!(Liquidator::held_debt(KSM, DOT), 0);
+		assert_eq!(Vault::borrow_balance(DOT, &liquidator_account()), 0);
+	});
+}
+
+#[test]
+fn keeper_submits_liquidations_once_per_interval() {
+	let mut ext = new_test_ext();
+	let (offchain, _) = testing::TestOffchainExt::new();
+	let (pool, pool_state) = testing::TestTransactionPoolExt::new();
+	ext.register_extension(OffchainDbExt::new(offchain.clone()));
+	ext.register_extension(OffchainWorkerExt::new(offchain));
+	ext.register_extension(TransactionPoolExt::new(pool));
+	UintAuthorityId::set_all_keys(vec![CHARLIE]);
+
+	ext.execute_with(|| {
+		open_position();
+		Liquidator::offchain_worker(1);
+		assert!(pool_state.read().transactions.is_empty());
+
+		// Runs are rate limited even when the previous one found nothing to liquidate
+		assert_ok!(Liquidator::set_collateral_params(Origin::root(), KSM, params(10, 10)));
+		assert_eq!(Liquidator::keeper_orders(None), (vec![(BOB, DOT, KSM, 100)], None));
+		Liquidator::offchain_worker(2);
+		assert!(pool_state.read().transactions.is_empty());
+
+		Liquidator::offchain_worker(6);
+		let tx = pool_state.write().transactions.pop().unwrap();
+		assert!(pool_state.read().transactions.is_empty());
+		let tx = UncheckedExtrinsic::decode(&mut &*tx).unwrap();
+		assert_eq!(tx.signature.unwrap().0, CHARLIE);
+		assert_eq!(tx.function, Call::Liquidator(crate::Call::liquidate(BOB, DOT, KSM, 100)));
+
+		Liquidator::offchain_worker(7);
+		assert!(pool_state.read().transactions.is_empty());
+		Liquidator::offchain_worker(11);
+		assert_eq!(pool_state.read().transactions.len(), 1);
+	});
+}
+
+#[test]
+fn keeper_checks_a_bounded_number_of_borrowers_per_run() {
+	new_test_ext().execute_with(|| {
+		open_position();
+		Currencies::mint(KSM, &CHARLIE, 500).unwrap();
+		assert_ok!(Liquidator::deposit_collateral(Origin::signed(CHARLIE), KSM, 500));
+		assert_ok!(Liquidator::borrow(Origin::signed(CHARLIE), DOT, 200));
+		assert_ok!(Liquidator::set_collateral_params(Origin::root(), KSM, params(10, 10)));
+		MaxKeeperScan::set(1);
+
+		let (first, cursor) = Liquidator::keeper_orders(None);
+		assert_eq!(first.len(), 1);
+		let (second, cursor) = Liquidator::keeper_orders(cursor);
+		assert_eq!(second.len(), 1);
+		assert_ne!(first, second);
+		// Every borrower has been checked, so the next run starts over
+		assert_eq!(Liquidator::keeper_orders(cursor), (vec![], None));
+		assert_eq!(Liquidator::keeper_orders(None).0, first);
 	});
 }
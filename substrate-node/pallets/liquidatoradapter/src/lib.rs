@@ -1,75 +1,214 @@
 #![cfg_attr(not(feature = "std"), no_std)]
-use frame_support::{decl_error, decl_event, decl_module, decl_storage, ensure, traits::{Get}};
-use frame_system::{ensure_signed};
-use sp_runtime::{
-	traits::{
-		Zero, AccountIdConversion
-	},
-	ModuleId,
-};
-
-use pallet_assets as Assets;
-
-pub trait Config: Assets::Config {
-    type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
-    
-    type LiquidatorModuleId: Get<ModuleId>;
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+mod strategy;
+pub use strategy::{DirectSwap, HoldForSale, LiquidationStrategy, StrategyKind, Swap};
+
+use codec::{Decode, Encode};
+use pallet_currencies::MultiCurrency;
+use sp_runtime::RuntimeDebug;
+use sp_std::prelude::*;
+
+/// Storage layout versions of the liquidator adapter.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum Releases {
+    /// First FRAME v2 layout
+    V1_0_0,
 }
 
-// 3. Storage
-decl_storage! {
-    trait Store for Module<T: Config> as LiquidatorAdapter {
+impl Default for Releases {
+    fn default() -> Self {
+        Releases::V1_0_0
     }
 }
 
-// 4. Events
-decl_event! {
-    pub enum Event<T> where AssetId = <T as Assets::Config>::AssetId, 
-    Balance = <T as Assets::Config>::Balance  {
-        /// Event emitted when liquidation occurs
-        Liquidated(AssetId, Balance, AssetId, Balance),
+#[frame_support::pallet]
+pub mod pallet {
+    use super::*;
+    use frame_support::{pallet_prelude::*, PalletId};
+    use frame_system::pallet_prelude::*;
+    use sp_runtime::traits::{AccountIdConversion, AtLeast32BitUnsigned, MaybeSerializeDeserialize, Member};
+
+    #[pallet::config]
+    pub trait Config: frame_system::Config {
+        type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+        type AssetId: Member + Parameter + Default + Copy + MaybeSerializeDeserialize;
+
+        type Balance: Member + Parameter + AtLeast32BitUnsigned + Default + Copy + MaybeSerializeDeserialize;
+
+        /// Currencies the liquidator holds the seized collateral in.
+        type Currencies: MultiCurrency<Self::AccountId, AssetId = Self::AssetId, Balance = Self::Balance>;
+
+        /// The liquidator's pallet id, only its account may execute liquidations.
+        #[pallet::constant]
+        type LiquidatorPalletId: Get<PalletId>;
+
+        /// Origin allowed to select liquidation strategies.
+        type AdminOrigin: EnsureOrigin<Self::Origin>;
+
+        /// Market used by the direct swap strategy.
+        type Swap: Swap<Self::AccountId, Self::AssetId, Self::Balance>;
+    }
+
+    #[pallet::pallet]
+    #[pallet::generate_store(pub(super) trait Store)]
+    pub struct Pallet<T>(_);
+
+    /// Strategy selling the collateral of a pair of debt and collateral assets
+    #[pallet::storage]
+    #[pallet::getter(fn strategy)]
+    pub type Strategies<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AssetId,
+        Blake2_128Concat,
+        T::AssetId,
+        StrategyKind,
+        ValueQuery,
+    >;
+
+    /// Storage layout version, used to migrate from older releases
+    #[pallet::storage]
+    pub(super) type StorageVersion<T> = StorageValue<_, Releases, ValueQuery>;
+
+    #[pallet::genesis_config]
+    pub struct GenesisConfig<T: Config> {
+        /// Strategies of pairs of debt and collateral assets set at genesis
+        pub strategies: Vec<(T::AssetId, T::AssetId, StrategyKind)>,
+    }
+
+    #[cfg(feature = "std")]
+    impl<T: Config> Default for GenesisConfig<T> {
+        fn default() -> Self {
+            Self { strategies: Vec::new() }
+        }
+    }
+
+    #[pallet::genesis_build]
+    impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+        fn build(&self) {
+            for (pay_asset_id, get_asset_id, strategy) in &self.strategies {
+                Strategies::<T>::insert(pay_asset_id, get_asset_id, strategy);
+            }
+            StorageVersion::<T>::put(Releases::V1_0_0);
+        }
+    }
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+
+    #[pallet::event]
+    #[pallet::metadata(T::AssetId = "AssetId", T::Balance = "Balance")]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
+        /// Event emitted when seized collateral is sold [pay_asset_id, received, get_asset_id, get_asset_amount]
+        Liquidated(T::AssetId, T::Balance, T::AssetId, T::Balance),
+        /// Event emitted when the strategy of a pair is set [pay_asset_id, get_asset_id, strategy]
+        StrategySet(T::AssetId, T::AssetId, StrategyKind),
     }
-}
 
-// 5. Errors
-decl_error! {
-    pub enum Error for Module<T: Config> {
-        /// Non existing asset
-        ExistingAsset,
+    #[pallet::error]
+    pub enum Error<T> {
+        /// Only the liquidator account may sell collateral
         NotLiquidator,
+        /// Strategy received less than the minimum
+        InsufficientProceeds,
+        /// The liquidator holds less collateral than it sells
+        InsufficientCollateral,
     }
-}
 
-// 6. Callable Functions
-decl_module! {
-    pub struct Module<T: Config> for enum Call where origin: T::Origin {
-        // Errors must be initialized if they are used by the pallet.
-        type Error = Error<T>;
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        #[pallet::weight(700_000)]
+        pub fn set_strategy(
+            origin: OriginFor<T>,
+            pay_asset_id: T::AssetId,
+            get_asset_id: T::AssetId,
+            strategy: StrategyKind,
+        ) -> DispatchResultWithPostInfo {
+            T::AdminOrigin::ensure_origin(origin)?;
+            Strategies::<T>::insert(pay_asset_id, get_asset_id, strategy);
+            Self::deposit_event(Event::StrategySet(pay_asset_id, get_asset_id, strategy));
+            Ok(().into())
+        }
+    }
 
-        // Events must be initialized if they are used by the pallet.
-        fn deposit_event() = default;
+    impl<T: Config> Pallet<T> {
+        pub fn liquidator_account_id() -> T::AccountId {
+            T::LiquidatorPalletId::get().into_account()
+        }
 
-        const LiquidatorModuleId: ModuleId = T::LiquidatorModuleId::get();
+        /// Debt asset the strategy of the pair would receive for `get_asset_amount` of collateral.
+        pub fn quote(
+            pay_asset_id: T::AssetId,
+            get_asset_id: T::AssetId,
+            get_asset_amount: T::Balance,
+        ) -> Result<T::Balance, DispatchError> {
+            match Strategies::<T>::get(pay_asset_id, get_asset_id) {
+                StrategyKind::HoldForSale => {
+                    <HoldForSale as LiquidationStrategy<T::AccountId, _, _>>::quote(pay_asset_id, get_asset_id, get_asset_amount)
+                }
+                StrategyKind::DirectSwap => {
+                    <DirectSwap<T::Swap> as LiquidationStrategy<T::AccountId, _, _>>::quote(pay_asset_id, get_asset_id, get_asset_amount)
+                }
+            }
+        }
+
+        /// Sell `get_asset_amount` of the collateral held by `who` with the strategy of the pair,
+        /// returning the debt asset received.
+        pub fn do_liquidate(
+            who: &T::AccountId,
+            pay_asset_id: T::AssetId,
+            get_asset_id: T::AssetId,
+            get_asset_amount: T::Balance,
+            min_received: T::Balance,
+        ) -> Result<T::Balance, DispatchError> {
+            let strategy = Strategies::<T>::get(pay_asset_id, get_asset_id);
+            Self::execute(strategy, who, pay_asset_id, get_asset_id, get_asset_amount, min_received)
+        }
 
-        #[weight = 700_000]
-        pub fn liquidate(origin, target_user: T::AccountId, pay_asset_id: T::AssetId, get_asset_id: T::AssetId, pay_asset_amount: T::Balance) {
-            // TODO: Execute strategy
-            let sender = ensure_signed(origin)?;
-            ensure!(sender == Self::liquidator_account_id(), Error::<T>::NotLiquidator);
-            let get_asset_amount: T::Balance = 2;
-            Self::deposit_event(RawEvent::Liquidated(pay_asset_id, pay_asset_amount, get_asset_id, get_asset_amount));
+        /// Sell `get_asset_amount` of the collateral held by `who` on the swap market whatever
+        /// the strategy of the pair, returning the debt asset received.
+        pub fn do_sell(
+            who: &T::AccountId,
+            pay_asset_id: T::AssetId,
+            get_asset_id: T::AssetId,
+            get_asset_amount: T::Balance,
+            min_received: T::Balance,
+        ) -> Result<T::Balance, DispatchError> {
+            Self::execute(StrategyKind::DirectSwap, who, pay_asset_id, get_asset_id, get_asset_amount, min_received)
         }
 
-        #[weight = 700_000]
-        fn asset_price_adapter(origin, asset_id: T::AssetId) -> T::Balance {
-            /// TODO: Add offchain price fetch
-            2 as u64
+        fn execute(
+            strategy: StrategyKind,
+            who: &T::AccountId,
+            pay_asset_id: T::AssetId,
+            get_asset_id: T::AssetId,
+            get_asset_amount: T::Balance,
+            min_received: T::Balance,
+        ) -> Result<T::Balance, DispatchError> {
+            ensure!(*who == Self::liquidator_account_id(), Error::<T>::NotLiquidator);
+            T::Currencies::ensure_can_withdraw(get_asset_id, who, get_asset_amount)
+                .map_err(|_| Error::<T>::InsufficientCollateral)?;
+            let received = match strategy {
+                StrategyKind::HoldForSale => {
+                    HoldForSale::execute(who, pay_asset_id, get_asset_id, get_asset_amount, min_received)?
+                }
+                StrategyKind::DirectSwap => {
+                    DirectSwap::<T::Swap>::execute(who, pay_asset_id, get_asset_id, get_asset_amount, min_received)?
+                }
+            };
+            ensure!(received >= min_received, Error::<T>::InsufficientProceeds);
+            Self::deposit_event(Event::Liquidated(pay_asset_id, received, get_asset_id, get_asset_amount));
+            Ok(received)
         }
     }
 }
-
-impl<T: Config> Module<T> {
-    pub fn liquidator_account_id() -> T::AccountId {
-		T::LiquidatorModuleId::get().into_account()
-	}
-}
\ No newline at end of file
@@ -1,14 +1,27 @@
-use crate as pallet_template;
+use crate as pallet_liquidator_adapter;
 use sp_core::H256;
-use frame_support::parameter_types;
+use frame_support::{parameter_types, PalletId};
 use sp_runtime::{
-	traits::{BlakeTwo256, IdentityLookup}, testing::Header,
+	traits::{AccountIdConversion, BlakeTwo256, IdentityLookup}, testing::Header, DispatchError,
 };
 use frame_system as system;
+use pallet_currencies::MultiCurrency;
 
 type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
 type Block = frame_system::mocking::MockBlock<Test>;
 
+pub const ALICE: u64 = 1;
+/// Counterparty of the mock swap market.
+pub const MARKET: u64 = 2;
+
+/// Debt asset repaid by the liquidator.
+pub const DOT: u32 = 0;
+/// Collateral asset seized by the liquidator.
+pub const KSM: u32 = 1;
+
+/// Balances held in storage, without a backing pallet.
+pub type Currencies = pallet_currencies::InMemoryCurrencies<u32, u64>;
+
 // Configure a mock runtime to test the pallet.
 frame_support::construct_runtime!(
 	pub enum Test where
@@ -17,7 +30,7 @@ frame_support::construct_runtime!(
 		UncheckedExtrinsic = UncheckedExtrinsic,
 	{
 		System: frame_system::{Module, Call, Config, Storage, Event<T>},
-		TemplateModule: pallet_template::{Module, Call, Storage, Event<T>},
+		LiquidatorAdapter: pallet_liquidator_adapter::{Module, Call, Storage, Config<T>, Event<T>},
 	}
 );
 
@@ -51,11 +64,49 @@ impl system::Config for Test {
 	type SS58Prefix = SS58Prefix;
 }
 
-impl pallet_template::Config for Test {
+/// Swaps with `MARKET`, paying twice the amount sold.
+pub struct MockSwap;
+
+impl pallet_liquidator_adapter::Swap<u64, u32, u64> for MockSwap {
+	fn quote_swap(_: u32, _: u32, amount_in: u64) -> Result<u64, DispatchError> {
+		Ok(amount_in * 2)
+	}
+
+	fn swap(who: &u64, asset_in: u32, asset_out: u32, amount_in: u64, _: u64) -> Result<u64, DispatchError> {
+		let amount_out = Self::quote_swap(asset_in, asset_out, amount_in)?;
+		Currencies::transfer(asset_in, who, &MARKET, amount_in)?;
+		Currencies::transfer(asset_out, &MARKET, who, amount_out)?;
+		Ok(amount_out)
+	}
+}
+
+parameter_types! {
+	pub const LiquidatorPalletId: PalletId = PalletId(*b"rp/liqtr");
+}
+
+impl pallet_liquidator_adapter::Config for Test {
 	type Event = Event;
+	type AssetId = u32;
+	type Balance = u64;
+	type Currencies = Currencies;
+	type LiquidatorPalletId = LiquidatorPalletId;
+	type AdminOrigin = frame_system::EnsureRoot<u64>;
+	type Swap = MockSwap;
+}
+
+pub fn liquidator_account() -> u64 {
+	LiquidatorPalletId::get().into_account()
 }
 
 // Build genesis storage according to the mock runtime.
 pub fn new_test_ext() -> sp_io::TestExternalities {
-	system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
+	let t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| {
+		System::set_block_number(1);
+		Currencies::mint(KSM, &liquidator_account(), 100).unwrap();
+		Currencies::mint(DOT, &MARKET, 1_000).unwrap();
+	});
+	ext
 }
@@ -0,0 +1,96 @@
+use codec::{Decode, Encode};
+use sp_runtime::{DispatchError, RuntimeDebug};
+use sp_std::marker::PhantomData;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+
+/// Converts collateral seized by the liquidator into the debt asset it repaid.
+pub trait LiquidationStrategy<AccountId, AssetId, Balance> {
+    /// Debt asset received for selling `get_amount` of collateral.
+    fn quote(pay_asset_id: AssetId, get_asset_id: AssetId, get_amount: Balance) -> Result<Balance, DispatchError>;
+
+    /// Sell `get_amount` of the collateral held by `who`, returning the debt asset it received.
+    fn execute(
+        who: &AccountId,
+        pay_asset_id: AssetId,
+        get_asset_id: AssetId,
+        get_amount: Balance,
+        min_received: Balance,
+    ) -> Result<Balance, DispatchError>;
+}
+
+/// Market exchanging one asset for another.
+pub trait Swap<AccountId, AssetId, Balance> {
+    /// Amount of `asset_out` received for `amount_in` of `asset_in`.
+    fn quote_swap(asset_in: AssetId, asset_out: AssetId, amount_in: Balance) -> Result<Balance, DispatchError>;
+
+    /// Exchange `amount_in` of `asset_in` held by `who` for at least `min_out` of `asset_out`.
+    fn swap(
+        who: &AccountId,
+        asset_in: AssetId,
+        asset_out: AssetId,
+        amount_in: Balance,
+        min_out: Balance,
+    ) -> Result<Balance, DispatchError>;
+}
+
+impl<AccountId, AssetId, Balance> Swap<AccountId, AssetId, Balance> for () {
+    fn quote_swap(_: AssetId, _: AssetId, _: Balance) -> Result<Balance, DispatchError> {
+        Err(DispatchError::Other("no swap market"))
+    }
+
+    fn swap(_: &AccountId, _: AssetId, _: AssetId, _: Balance, _: Balance) -> Result<Balance, DispatchError> {
+        Err(DispatchError::Other("no swap market"))
+    }
+}
+
+/// Strategy selected for a pair of debt and collateral assets.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum StrategyKind {
+    /// Keep the collateral in the liquidator account until governance sells it
+    HoldForSale,
+    /// Sell the collateral for the debt asset right away
+    DirectSwap,
+}
+
+impl Default for StrategyKind {
+    fn default() -> Self {
+        StrategyKind::DirectSwap
+    }
+}
+
+/// Keeps the collateral in the liquidator account for a later sale, receiving nothing.
+pub struct HoldForSale;
+
+impl<AccountId, AssetId, Balance: Default> LiquidationStrategy<AccountId, AssetId, Balance> for HoldForSale {
+    fn quote(_: AssetId, _: AssetId, _: Balance) -> Result<Balance, DispatchError> {
+        Ok(Balance::default())
+    }
+
+    fn execute(_: &AccountId, _: AssetId, _: AssetId, _: Balance, _: Balance) -> Result<Balance, DispatchError> {
+        Ok(Balance::default())
+    }
+}
+
+/// Sells the collateral for the debt asset on the market `S`.
+pub struct DirectSwap<S>(PhantomData<S>);
+
+impl<AccountId, AssetId, Balance, S> LiquidationStrategy<AccountId, AssetId, Balance> for DirectSwap<S>
+where
+    S: Swap<AccountId, AssetId, Balance>,
+{
+    fn quote(pay_asset_id: AssetId, get_asset_id: AssetId, get_amount: Balance) -> Result<Balance, DispatchError> {
+        S::quote_swap(get_asset_id, pay_asset_id, get_amount)
+    }
+
+    fn execute(
+        who: &AccountId,
+        pay_asset_id: AssetId,
+        get_asset_id: AssetId,
+        get_amount: Balance,
+        min_received: Balance,
+    ) -> Result<Balance, DispatchError> {
+        S::swap(who, get_asset_id, pay_asset_id, get_amount, min_received)
+    }
+}
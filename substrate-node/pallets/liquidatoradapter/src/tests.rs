@@ -1,23 +1,49 @@
-use crate::{Error, mock::*};
+use crate::{Error, StrategyKind, mock::*};
 use frame_support::{assert_ok, assert_noop};
+use pallet_currencies::MultiCurrency;
+use sp_runtime::DispatchError;
 
 #[test]
-fn it_works_for_default_value() {
+fn strategies_default_to_swapping_and_are_set_by_admin() {
 	new_test_ext().execute_with(|| {
-		// Dispatch a signed extrinsic.
-		assert_ok!(TemplateModule::do_something(Origin::signed(1), 42));
-		// Read pallet storage and assert an expected result.
-		assert_eq!(TemplateModule::something(), Some(42));
+		assert_eq!(LiquidatorAdapter::strategy(DOT, KSM), StrategyKind::DirectSwap);
+		assert_noop!(
+			LiquidatorAdapter::set_strategy(Origin::signed(ALICE), DOT, KSM, StrategyKind::HoldForSale),
+			DispatchError::BadOrigin
+		);
+		assert_ok!(LiquidatorAdapter::set_strategy(Origin::root(), DOT, KSM, StrategyKind::HoldForSale));
+		assert_eq!(LiquidatorAdapter::strategy(DOT, KSM), StrategyKind::HoldForSale);
+		assert!(System::events().iter().any(|record| record.event == Event::pallet_liquidator_adapter(
+			crate::Event::StrategySet(DOT, KSM, StrategyKind::HoldForSale)
+		)));
 	});
 }
 
 #[test]
-fn correct_error_for_none_value() {
+fn holding_keeps_the_collateral() {
 	new_test_ext().execute_with(|| {
-		// Ensure the expected error is thrown when no value is present.
+		assert_ok!(LiquidatorAdapter::set_strategy(Origin::root(), DOT, KSM, StrategyKind::HoldForSale));
 		assert_noop!(
-			TemplateModule::cause_error(Origin::signed(1)),
-			Error::<Test>::NoneValue
+			LiquidatorAdapter::do_liquidate(&ALICE, DOT, KSM, 10, 0),
+			Error::<Test>::NotLiquidator
 		);
+		assert_noop!(
+			LiquidatorAdapter::do_liquidate(&liquidator_account(), DOT, KSM, 101, 0),
+			Error::<Test>::InsufficientCollateral
+		);
+		assert_eq!(LiquidatorAdapter::do_liquidate(&liquidator_account(), DOT, KSM, 40, 0), Ok(0));
+		assert_eq!(Currencies::free_balance(KSM, &liquidator_account()), 100);
+	});
+}
+
+#[test]
+fn direct_swap_sells_the_collateral() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(LiquidatorAdapter::set_strategy(Origin::root(), DOT, KSM, StrategyKind::DirectSwap));
+		assert_eq!(LiquidatorAdapter::quote(DOT, KSM, 40), Ok(80));
+		assert_eq!(LiquidatorAdapter::do_liquidate(&liquidator_account(), DOT, KSM, 40, 80), Ok(80));
+		assert_eq!(Currencies::free_balance(KSM, &liquidator_account()), 60);
+		assert_eq!(Currencies::free_balance(DOT, &liquidator_account()), 80);
+		assert_eq!(Currencies::free_balance(KSM, &MARKET), 40);
 	});
 }
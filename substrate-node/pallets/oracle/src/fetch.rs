@@ -0,0 +1,218 @@
+//! Offchain worker fetching prices over HTTP.
+//!
+//! Price sources are read from the node's persistent local storage under [`SOURCES_KEY`], as a
+//! SCALE encoded `Vec<PriceSource>`, so operators configure them with `offchain_localStorageSet`.
+//! Each source names a URL answering JSON and the dot separated path of the price in it, e.g.
+//! `data.0.price`, where the price is a number or a string holding one.
+//!
+//! The worker signs with the first key of type [`KEY_TYPE`] found in the node keystore. Prices
+//! are submitted as signed `feed_price` transactions, or as one unsigned `feed_prices_unsigned`
+//! transaction carrying a signed payload when [`UNSIGNED_KEY`] holds `true`.
+
+use super::*;
+use frame_support::traits::Get;
+use frame_system::offchain::{SendSignedTransaction, SendUnsignedTransaction, SignedPayload, Signer, SigningTypes};
+use lite_json::json::{JsonValue, NumberValue};
+use sp_runtime::offchain::{
+    http,
+    storage::StorageValueRef,
+    storage_lock::{BlockAndTime, StorageLock},
+    Duration,
+};
+use sp_runtime::KeyTypeId;
+
+/// Key type of the keystore keys signing price feeds.
+pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"orcl");
+
+/// Local storage key of the SCALE encoded `Vec<PriceSource>` to fetch.
+pub const SOURCES_KEY: &[u8] = b"oracle::fetch::sources";
+/// Local storage key of the SCALE encoded `bool` selecting unsigned submissions.
+pub const UNSIGNED_KEY: &[u8] = b"oracle::fetch::unsigned";
+/// Local storage key of the block prices were last fetched at.
+const LAST_RUN_KEY: &[u8] = b"oracle::fetch::last-run";
+/// Local storage key of the lock held while a fetch is in progress.
+const LOCK_KEY: &[u8] = b"oracle::fetch::lock";
+/// Blocks after which a lock left by a crashed run expires.
+const LOCK_BLOCK_EXPIRATION: u32 = 3;
+/// Milliseconds after which a lock left by a crashed run expires.
+const LOCK_TIMEOUT_EXPIRATION: u64 = 10_000;
+/// Milliseconds a single HTTP request may take.
+const FETCH_TIMEOUT: u64 = 2_000;
+
+/// Feeder key crypto, sr25519 signatures verified as the runtime `MultiSignature`.
+pub mod crypto {
+    use super::KEY_TYPE;
+    use sp_runtime::{
+        app_crypto::{app_crypto, sr25519},
+        MultiSignature, MultiSigner,
+    };
+
+    app_crypto!(sr25519, KEY_TYPE);
+
+    pub struct FeederAuthId;
+
+    impl frame_system::offchain::AppCrypto<MultiSigner, MultiSignature> for FeederAuthId {
+        type RuntimeAppPublic = Public;
+        type GenericSignature = sp_core::sr25519::Signature;
+        type GenericPublic = sp_core::sr25519::Public;
+    }
+}
+
+/// An HTTP endpoint quoting the price of one whole unit of an asset.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct PriceSource<AssetId> {
+    pub asset_id: AssetId,
+    /// URL answering JSON
+    pub url: Vec<u8>,
+    /// Dot separated object keys and array indices leading to the price
+    pub path: Vec<u8>,
+}
+
+/// Prices fetched by a feeder, signed with its key for unsigned submission.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct PricePayload<Public, AssetId, BlockNumber> {
+    pub public: Public,
+    /// Block the prices were fetched at
+    pub block_number: BlockNumber,
+    pub prices: Vec<(AssetId, FixedU128)>,
+}
+
+impl<T: SigningTypes, AssetId: Encode> SignedPayload<T> for PricePayload<T::Public, AssetId, T::BlockNumber> {
+    fn public(&self) -> T::Public {
+        self.public.clone()
+    }
+}
+
+impl<T: Config> Pallet<T> {
+    /// Fetch the configured prices and submit them, at most once every `FetchInterval` blocks.
+    pub(crate) fn fetch_and_submit(now: T::BlockNumber) -> Result<(), &'static str> {
+        let signer = Signer::<T, T::AuthorityId>::any_account();
+        if !signer.can_sign() {
+            return Ok(());
+        }
+        let sources = match StorageValueRef::persistent(SOURCES_KEY).get::<Vec<PriceSource<T::AssetId>>>() {
+            Some(Some(sources)) if !sources.is_empty() => sources,
+            Some(None) => return Err("invalid price sources"),
+            _ => return Ok(()),
+        };
+
+        // Workers of consecutive blocks may overlap, only one of them fetches at a time
+        let mut lock = StorageLock::<BlockAndTime<frame_system::Pallet<T>>>::with_block_and_time_deadline(
+            LOCK_KEY,
+            LOCK_BLOCK_EXPIRATION,
+            Duration::from_millis(LOCK_TIMEOUT_EXPIRATION),
+        );
+        let _guard = lock.try_lock().map_err(|_| "price fetch already running")?;
+
+        let last_run = StorageValueRef::persistent(LAST_RUN_KEY);
+        let interval = T::FetchInterval::get();
+        let res = last_run.mutate(|last: Option<Option<T::BlockNumber>>| match last {
+            Some(Some(block)) if now < block.saturating_add(interval) => Err(()),
+            _ => Ok(now),
+        });
+        match res {
+            Ok(Ok(_)) => {}
+            Err(()) => return Ok(()),
+            Ok(Err(_)) => return Err("failed to record price fetch"),
+        }
+
+        let prices: Vec<(T::AssetId, FixedU128)> = sources
+            .iter()
+            .take(T::MaxPricesPerFeed::get() as usize)
+            .filter_map(|source| match Self::fetch_price(source) {
+                Ok(price) => Some((source.asset_id, price)),
+                Err(e) => {
+                    log::warn!(target: "oracle", "fetching the price of {:?} failed: {:?}", source.asset_id, e);
+                    None
+                }
+            })
+            .collect();
+        if prices.is_empty() {
+            return Ok(());
+        }
+
+        let unsigned = StorageValueRef::persistent(UNSIGNED_KEY).get::<bool>().flatten().unwrap_or(false);
+        if unsigned {
+            let result = signer.send_unsigned_transaction(
+                |account| PricePayload { public: account.public.clone(), block_number: now, prices: prices.clone() },
+                |payload, signature| Call::feed_prices_unsigned(payload, signature),
+            );
+            match result {
+                Some((_, Ok(()))) => {}
+                _ => return Err("failed to submit unsigned prices"),
+            }
+        } else {
+            for (asset_id, price) in prices {
+                match signer.send_signed_transaction(|_| Call::feed_price(asset_id, price)) {
+                    Some((_, Ok(()))) => {}
+                    _ => return Err("failed to submit price"),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Price quoted by `source`.
+    fn fetch_price(source: &PriceSource<T::AssetId>) -> Result<FixedU128, http::Error> {
+        let url = sp_std::str::from_utf8(&source.url).map_err(|_| http::Error::Unknown)?;
+        let deadline = sp_io::offchain::timestamp().add(Duration::from_millis(FETCH_TIMEOUT));
+        let pending = http::Request::get(url).deadline(deadline).send().map_err(|_| http::Error::IoError)?;
+        let response = pending.try_wait(deadline).map_err(|_| http::Error::DeadlineReached)??;
+        if response.code != 200 {
+            log::warn!(target: "oracle", "unexpected status code {} from {}", response.code, url);
+            return Err(http::Error::Unknown);
+        }
+        let body = response.body().collect::<Vec<u8>>();
+        let body = sp_std::str::from_utf8(&body).map_err(|_| http::Error::Unknown)?;
+        Self::parse_price(body, &source.path).ok_or(http::Error::Unknown)
+    }
+
+    /// Price found at the dot separated `path` of the JSON `body`.
+    pub fn parse_price(body: &str, path: &[u8]) -> Option<FixedU128> {
+        let mut value = lite_json::parse_json(body).ok()?;
+        for key in path.split(|c| *c == b'.').filter(|key| !key.is_empty()) {
+            value = match value {
+                JsonValue::Object(fields) => fields
+                    .into_iter()
+                    .find(|(name, _)| name.iter().copied().eq(key.iter().map(|c| *c as char)))
+                    .map(|(_, field)| field)?,
+                JsonValue::Array(items) => {
+                    let index: usize = sp_std::str::from_utf8(key).ok()?.parse().ok()?;
+                    items.into_iter().nth(index)?
+                }
+                _ => return None,
+            };
+        }
+        match value {
+            JsonValue::Number(number) => Self::number_to_fixed(number),
+            JsonValue::String(chars) => {
+                let text = chars
+                    .iter()
+                    .map(|c| if c.is_ascii() { Some(*c as u8) } else { None })
+                    .collect::<Option<Vec<u8>>>()?;
+                match lite_json::parse_json(sp_std::str::from_utf8(&text).ok()?).ok()? {
+                    JsonValue::Number(number) => Self::number_to_fixed(number),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn number_to_fixed(number: NumberValue) -> Option<FixedU128> {
+        if number.integer < 0 {
+            return None;
+        }
+        let fraction_unit = 10u128.checked_pow(number.fraction_length)?;
+        let integer = FixedU128::checked_from_integer(number.integer as u128)?;
+        let fraction = FixedU128::checked_from_rational(number.fraction as u128, fraction_unit)?;
+        let value = integer.checked_add(&fraction)?;
+
+        let scale = FixedU128::checked_from_integer(10u128.checked_pow(number.exponent.checked_abs()? as u32)?)?;
+        if number.exponent < 0 {
+            value.checked_div(&scale)
+        } else {
+            value.checked_mul(&scale)
+        }
+    }
+}
@@ -0,0 +1,429 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Asset price oracle.
+//!
+//! Authorized feeders submit the price of one whole unit of an asset. The oracle price is the
+//! median of the feeds that are not older than the staleness threshold, and is itself considered
+//! stale once the threshold has passed without a new feed.
+//!
+//! The oracle also tracks time weighted average prices. An update deviating from the average by
+//! more than `MaxPriceDeviation` trips the circuit breaker of the asset until the admin resets it,
+//! which consumers use to pause actions relying on its price.
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+mod fetch;
+pub use fetch::{crypto, PricePayload, PriceSource, KEY_TYPE, SOURCES_KEY, UNSIGNED_KEY};
+
+mod twap;
+pub use twap::Observation;
+
+use sp_std::prelude::*;
+use sp_std::convert::TryInto;
+use codec::{Encode, Decode};
+use sp_runtime::{
+    helpers_128bit::multiply_by_rational,
+    traits::{AtLeast32BitUnsigned, Bounded, CheckedSub, Saturating, Zero},
+    FixedPointNumber, FixedU128, Permill, RuntimeDebug, SaturatedConversion,
+};
+
+/// Prices assets in a common quote currency.
+pub trait PriceProvider<AssetId, Balance> {
+    /// Price of one whole unit of `asset_id`, or `None` if there is no fresh price.
+    fn price(asset_id: AssetId) -> Option<FixedU128>;
+
+    /// Value of `amount` of `asset_id` in its smallest unit.
+    fn value(asset_id: AssetId, amount: Balance) -> Option<FixedU128>;
+
+    /// Amount of `asset_id` in its smallest unit worth `value`, rounded down, or `None` if it does
+    /// not fit the balance type.
+    fn amount(asset_id: AssetId, value: FixedU128) -> Option<Balance>;
+
+    /// Time weighted average price of one whole unit of `asset_id`.
+    fn twap(asset_id: AssetId) -> Option<FixedU128>;
+
+    /// Whether the price of `asset_id` moved too abruptly to be acted on.
+    fn is_paused(asset_id: AssetId) -> bool;
+}
+
+/// A price and the block it was submitted in.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Default, RuntimeDebug)]
+pub struct TimestampedPrice<BlockNumber> {
+    pub price: FixedU128,
+    pub block: BlockNumber,
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+    use super::*;
+    use frame_support::pallet_prelude::*;
+    use frame_system::{offchain::{AppCrypto, CreateSignedTransaction, SignedPayload, SigningTypes}, pallet_prelude::*};
+    use codec::HasCompact;
+    use sp_runtime::traits::{IdentifyAccount, MaybeSerializeDeserialize, Member};
+
+    pub type PricePayloadOf<T> = PricePayload<
+        <T as SigningTypes>::Public,
+        <T as Config>::AssetId,
+        <T as frame_system::Config>::BlockNumber,
+    >;
+
+    #[pallet::config]
+    pub trait Config: CreateSignedTransaction<Call<Self>> + frame_system::Config {
+        type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+        type AssetId: Member + Parameter + Default + Copy + HasCompact + MaybeSerializeDeserialize;
+
+        /// Origin allowed to manage feeders and reset circuit breakers.
+        type AdminOrigin: EnsureOrigin<Self::Origin>;
+
+        /// Decimals metadata of the assets, shared with the currencies the prices are used for.
+        type Decimals: pallet_currencies::Decimals<Self::AssetId>;
+
+        /// Maximum number of feeders.
+        #[pallet::constant]
+        type MaxFeeders: Get<u32>;
+
+        /// Number of blocks after which a price is no longer used.
+        #[pallet::constant]
+        type StalenessThreshold: Get<Self::BlockNumber>;
+
+        /// Keystore key the offchain worker signs fetched prices with.
+        type AuthorityId: AppCrypto<Self::Public, Self::Signature>;
+
+        /// Blocks between two price fetches of the offchain worker, and between two unsigned
+        /// submissions of a feeder.
+        #[pallet::constant]
+        type FetchInterval: Get<Self::BlockNumber>;
+
+        /// Maximum number of prices in one unsigned submission.
+        #[pallet::constant]
+        type MaxPricesPerFeed: Get<u32>;
+
+        /// Priority of unsigned price submissions.
+        #[pallet::constant]
+        type UnsignedPriority: Get<TransactionPriority>;
+
+        /// Longest window time weighted average prices can be queried over.
+        #[pallet::constant]
+        type MaxTwapWindow: Get<Self::BlockNumber>;
+
+        /// Window of the average price the circuit breaker compares updates with, at most
+        /// `MaxTwapWindow`.
+        #[pallet::constant]
+        type TwapWindow: Get<Self::BlockNumber>;
+
+        /// Deviation from the average price that trips the circuit breaker.
+        #[pallet::constant]
+        type MaxPriceDeviation: Get<Permill>;
+    }
+
+    #[pallet::pallet]
+    #[pallet::generate_store(pub(super) trait Store)]
+    pub struct Pallet<T>(_);
+
+    /// Accounts allowed to feed prices, sorted
+    #[pallet::storage]
+    #[pallet::getter(fn feeders)]
+    pub type Feeders<T: Config> = StorageValue<_, Vec<T::AccountId>, ValueQuery>;
+
+    /// Last price submitted by each feeder per asset
+    #[pallet::storage]
+    #[pallet::getter(fn raw_price)]
+    pub type RawPrices<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AssetId,
+        Blake2_128Concat,
+        T::AccountId,
+        TimestampedPrice<T::BlockNumber>,
+        OptionQuery,
+    >;
+
+    /// Median of the fresh feeds per asset
+    #[pallet::storage]
+    #[pallet::getter(fn aggregated_price)]
+    pub type Prices<T: Config> = StorageMap<_, Blake2_128Concat, T::AssetId, TimestampedPrice<T::BlockNumber>, OptionQuery>;
+
+    /// Cumulative prices per asset, oldest first
+    #[pallet::storage]
+    #[pallet::getter(fn observations)]
+    pub type Observations<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AssetId, Vec<Observation<T::BlockNumber>>, ValueQuery>;
+
+    /// Block the circuit breaker of an asset tripped at
+    #[pallet::storage]
+    #[pallet::getter(fn circuit_breaker)]
+    pub type CircuitBreakers<T: Config> = StorageMap<_, Blake2_128Concat, T::AssetId, T::BlockNumber, OptionQuery>;
+
+    /// First block a feeder may submit unsigned prices fetched at
+    #[pallet::storage]
+    #[pallet::getter(fn next_unsigned_at)]
+    pub type NextUnsignedAt<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, T::BlockNumber, ValueQuery>;
+
+    #[pallet::genesis_config]
+    pub struct GenesisConfig<T: Config> {
+        pub feeders: Vec<T::AccountId>,
+    }
+
+    #[cfg(feature = "std")]
+    impl<T: Config> Default for GenesisConfig<T> {
+        fn default() -> Self {
+            Self { feeders: Vec::new() }
+        }
+    }
+
+    #[pallet::genesis_build]
+    impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+        fn build(&self) {
+            let mut feeders = self.feeders.clone();
+            feeders.sort();
+            feeders.dedup();
+            assert!(feeders.len() as u32 <= T::MaxFeeders::get(), "too many genesis feeders");
+            Feeders::<T>::put(feeders);
+        }
+    }
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn offchain_worker(now: T::BlockNumber) {
+            if let Err(e) = Self::fetch_and_submit(now) {
+                log::warn!(target: "oracle", "price fetch at block {:?} failed: {}", now, e);
+            }
+        }
+    }
+
+    #[pallet::event]
+    #[pallet::metadata(T::AccountId = "AccountId", T::AssetId = "AssetId")]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
+        /// Event emitted when a feeder is authorized [feeder]
+        FeederAdded(T::AccountId),
+        /// Event emitted when a feeder is removed [feeder]
+        FeederRemoved(T::AccountId),
+        /// Event emitted when a feeder submits a price [feeder, asset_id, price]
+        PriceFed(T::AccountId, T::AssetId, FixedU128),
+        /// Event emitted when the oracle price of an asset changes [asset_id, price]
+        PriceUpdated(T::AssetId, FixedU128),
+        /// Event emitted when a price deviates too far from the average [asset_id, price, twap]
+        CircuitBreakerTripped(T::AssetId, FixedU128, FixedU128),
+        /// Event emitted when the circuit breaker of an asset is reset [asset_id]
+        CircuitBreakerReset(T::AssetId),
+    }
+
+    #[pallet::error]
+    pub enum Error<T> {
+        /// Account is not an authorized feeder
+        NotFeeder,
+        /// Account is already a feeder
+        AlreadyFeeder,
+        /// Maximum number of feeders reached
+        TooManyFeeders,
+        /// Prices must be positive
+        ZeroPrice,
+        /// Circuit breaker of the asset is not tripped
+        CircuitBreakerNotTripped,
+    }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        #[pallet::weight(10_000)]
+        pub fn add_feeder(origin: OriginFor<T>, feeder: T::AccountId) -> DispatchResultWithPostInfo {
+            T::AdminOrigin::ensure_origin(origin)?;
+            let mut feeders = Feeders::<T>::get();
+            let index = match feeders.binary_search(&feeder) {
+                Ok(_) => return Err(Error::<T>::AlreadyFeeder.into()),
+                Err(index) => index,
+            };
+            ensure!((feeders.len() as u32) < T::MaxFeeders::get(), Error::<T>::TooManyFeeders);
+            feeders.insert(index, feeder.clone());
+            Feeders::<T>::put(feeders);
+
+            Self::deposit_event(Event::FeederAdded(feeder));
+            Ok(().into())
+        }
+
+        /// Remove a feeder, its past feeds are ignored from the next aggregation on.
+        #[pallet::weight(10_000)]
+        pub fn remove_feeder(origin: OriginFor<T>, feeder: T::AccountId) -> DispatchResultWithPostInfo {
+            T::AdminOrigin::ensure_origin(origin)?;
+            Feeders::<T>::try_mutate(|feeders| -> DispatchResult {
+                let index = feeders.binary_search(&feeder).map_err(|_| Error::<T>::NotFeeder)?;
+                feeders.remove(index);
+                Ok(())
+            })?;
+
+            Self::deposit_event(Event::FeederRemoved(feeder));
+            Ok(().into())
+        }
+
+        /// Resume the use of the price of an asset after its circuit breaker tripped.
+        #[pallet::weight(10_000)]
+        pub fn reset_circuit_breaker(origin: OriginFor<T>, asset_id: T::AssetId) -> DispatchResultWithPostInfo {
+            T::AdminOrigin::ensure_origin(origin)?;
+            ensure!(CircuitBreakers::<T>::contains_key(asset_id), Error::<T>::CircuitBreakerNotTripped);
+            CircuitBreakers::<T>::remove(asset_id);
+            Self::deposit_event(Event::CircuitBreakerReset(asset_id));
+            Ok(().into())
+        }
+
+        /// Submit the price of one whole unit of an asset and update its median.
+        #[pallet::weight(50_000 + 10_000 * T::MaxFeeders::get() as Weight)]
+        pub fn feed_price(origin: OriginFor<T>, asset_id: T::AssetId, price: FixedU128) -> DispatchResultWithPostInfo {
+            let sender = ensure_signed(origin)?;
+            Self::do_feed_price(sender, asset_id, price)?;
+            Ok(().into())
+        }
+
+        /// Submit prices fetched by the offchain worker of a feeder, signed with its key.
+        #[pallet::weight((50_000 + 10_000 * T::MaxFeeders::get() as Weight).saturating_mul(payload.prices.len() as Weight))]
+        pub fn feed_prices_unsigned(
+            origin: OriginFor<T>,
+            payload: PricePayloadOf<T>,
+            _signature: T::Signature,
+        ) -> DispatchResultWithPostInfo {
+            ensure_none(origin)?;
+            let feeder = payload.public.into_account();
+            let now = <frame_system::Pallet<T>>::block_number();
+            NextUnsignedAt::<T>::insert(&feeder, now.saturating_add(T::FetchInterval::get()));
+            for (asset_id, price) in payload.prices {
+                Self::do_feed_price(feeder.clone(), asset_id, price)?;
+            }
+            Ok(().into())
+        }
+    }
+
+    #[pallet::validate_unsigned]
+    impl<T: Config> ValidateUnsigned for Pallet<T> {
+        type Call = Call<T>;
+
+        fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+            if let Call::feed_prices_unsigned(payload, signature) = call {
+                Self::validate_price_payload(payload, signature)
+            } else {
+                InvalidTransaction::Call.into()
+            }
+        }
+    }
+
+    impl<T: Config> Pallet<T> {
+        /// Accept signed payloads of feeders, at most one per `FetchInterval` blocks each.
+        fn validate_price_payload(payload: &PricePayloadOf<T>, signature: &T::Signature) -> TransactionValidity {
+            if !SignedPayload::<T>::verify::<T::AuthorityId>(payload, signature.clone()) {
+                return InvalidTransaction::BadProof.into();
+            }
+            let feeder = payload.public.clone().into_account();
+            if !Self::is_feeder(&feeder) {
+                return InvalidTransaction::BadSigner.into();
+            }
+            if payload.prices.is_empty()
+                || payload.prices.len() as u32 > T::MaxPricesPerFeed::get()
+                || payload.prices.iter().any(|(_, price)| *price == Zero::zero())
+            {
+                return InvalidTransaction::Call.into();
+            }
+            if payload.block_number > <frame_system::Pallet<T>>::block_number() {
+                return InvalidTransaction::Future.into();
+            }
+            if payload.block_number < NextUnsignedAt::<T>::get(&feeder) {
+                return InvalidTransaction::Stale.into();
+            }
+
+            ValidTransaction::with_tag_prefix("OraclePrices")
+                .priority(T::UnsignedPriority::get())
+                .and_provides(feeder)
+                .longevity(T::FetchInterval::get().saturated_into::<u64>())
+                .propagate(true)
+                .build()
+        }
+    }
+}
+
+impl<T: Config> Pallet<T> {
+    pub fn is_feeder(who: &T::AccountId) -> bool {
+        Feeders::<T>::get().binary_search(who).is_ok()
+    }
+
+    pub fn do_feed_price(feeder: T::AccountId, asset_id: T::AssetId, price: FixedU128) -> DispatchResult {
+        frame_support::ensure!(Self::is_feeder(&feeder), Error::<T>::NotFeeder);
+        frame_support::ensure!(price > Zero::zero(), Error::<T>::ZeroPrice);
+
+        let now = <frame_system::Pallet<T>>::block_number();
+        RawPrices::<T>::insert(asset_id, &feeder, TimestampedPrice { price, block: now });
+        Self::deposit_event(Event::PriceFed(feeder, asset_id, price));
+
+        if let Some(median) = Self::median(asset_id) {
+            Self::observe(asset_id, now);
+            Self::check_circuit_breaker(asset_id, median, now);
+            Prices::<T>::insert(asset_id, TimestampedPrice { price: median, block: now });
+            Self::deposit_event(Event::PriceUpdated(asset_id, median));
+        }
+        Ok(())
+    }
+
+    /// Median of the fresh feeds of current feeders, averaging the middle two of an even count.
+    pub fn median(asset_id: T::AssetId) -> Option<FixedU128> {
+        let feeders = Feeders::<T>::get();
+        let mut prices: Vec<FixedU128> = RawPrices::<T>::iter_prefix(asset_id)
+            .filter(|(feeder, feed)| feeders.binary_search(feeder).is_ok() && Self::is_fresh(feed))
+            .map(|(_, feed)| feed.price)
+            .collect();
+        if prices.is_empty() {
+            return None;
+        }
+        prices.sort();
+
+        let mid = prices.len() / 2;
+        if prices.len() % 2 == 1 {
+            Some(prices[mid])
+        } else {
+            let sum = prices[mid - 1].saturating_add(prices[mid]);
+            Some(FixedU128::from_inner(sum.into_inner() / 2))
+        }
+    }
+
+    fn is_fresh(feed: &TimestampedPrice<T::BlockNumber>) -> bool {
+        let now = <frame_system::Pallet<T>>::block_number();
+        now <= feed.block.saturating_add(T::StalenessThreshold::get())
+    }
+
+    /// Number of smallest units in one whole unit of `asset_id`.
+    fn unit(asset_id: T::AssetId) -> Option<u128> {
+        10u128.checked_pow(<T::Decimals as pallet_currencies::Decimals<_>>::decimals(asset_id) as u32)
+    }
+}
+
+impl<T: Config, Balance: AtLeast32BitUnsigned> PriceProvider<T::AssetId, Balance> for Pallet<T> {
+    fn price(asset_id: T::AssetId) -> Option<FixedU128> {
+        Prices::<T>::get(asset_id)
+            .filter(|feed| Self::is_fresh(feed))
+            .map(|feed| feed.price)
+    }
+
+    fn value(asset_id: T::AssetId, amount: Balance) -> Option<FixedU128> {
+        let price = <Self as PriceProvider<T::AssetId, Balance>>::price(asset_id)?;
+        let units = FixedU128::checked_from_rational(amount.saturated_into::<u128>(), Self::unit(asset_id)?)?;
+        units.checked_mul(&price)
+    }
+
+    fn amount(asset_id: T::AssetId, value: FixedU128) -> Option<Balance> {
+        let price = <Self as PriceProvider<T::AssetId, Balance>>::price(asset_id)?;
+        let units = value.checked_div(&price)?;
+        multiply_by_rational(units.into_inner(), Self::unit(asset_id)?, FixedU128::accuracy())
+            .ok()
+            .and_then(|amount| amount.try_into().ok())
+    }
+
+    fn twap(asset_id: T::AssetId) -> Option<FixedU128> {
+        Self::twap(asset_id, T::TwapWindow::get())
+    }
+
+    fn is_paused(asset_id: T::AssetId) -> bool {
+        CircuitBreakers::<T>::contains_key(asset_id)
+    }
+}
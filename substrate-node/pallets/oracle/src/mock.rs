@@ -0,0 +1,142 @@
+use crate as pallet_oracle;
+use sp_core::H256;
+use frame_support::parameter_types;
+use sp_runtime::{
+	traits::{BlakeTwo256, Extrinsic as ExtrinsicT, IdentityLookup},
+	testing::{Header, TestSignature, UintAuthorityId}, transaction_validity::TransactionPriority, Permill,
+};
+use frame_system as system;
+use frame_system::offchain::{AppCrypto, CreateSignedTransaction, SendTransactionTypes, SigningTypes};
+
+pub type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+pub const ALICE: u64 = 1;
+pub const BOB: u64 = 2;
+pub const CHARLIE: u64 = 3;
+pub const DAVE: u64 = 4;
+
+/// Asset with 10 decimals.
+pub const DOT: u32 = 0;
+/// Asset with 12 decimals unless `KsmDecimals` is set.
+pub const KSM: u32 = 1;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Module, Call, Config, Storage, Event<T>},
+		Oracle: pallet_oracle::{Module, Call, Storage, Config<T>, Event<T>, ValidateUnsigned},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const SS58Prefix: u8 = 42;
+}
+
+impl system::Config for Test {
+	type BaseCallFilter = ();
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = SS58Prefix;
+}
+
+parameter_types! {
+	pub const MaxFeeders: u32 = 3;
+	pub const StalenessThreshold: u64 = 10;
+	pub const FetchInterval: u64 = 5;
+	pub const MaxPricesPerFeed: u32 = 2;
+	pub const UnsignedPriority: TransactionPriority = 100;
+	pub const MaxTwapWindow: u64 = 10;
+	pub const TwapWindow: u64 = 4;
+	pub const MaxPriceDeviation: Permill = Permill::from_percent(20);
+	pub static KsmDecimals: u8 = 12;
+}
+
+/// Gives DOT 10 decimals and KSM `KsmDecimals`.
+pub struct MockDecimals;
+
+impl pallet_currencies::Decimals<u32> for MockDecimals {
+	fn decimals(asset_id: u32) -> u8 {
+		if asset_id == KSM { KsmDecimals::get() } else { 10 }
+	}
+}
+
+pub struct TestAuthId;
+
+impl AppCrypto<UintAuthorityId, TestSignature> for TestAuthId {
+	type RuntimeAppPublic = UintAuthorityId;
+	type GenericSignature = TestSignature;
+	type GenericPublic = UintAuthorityId;
+}
+
+impl SigningTypes for Test {
+	type Public = UintAuthorityId;
+	type Signature = TestSignature;
+}
+
+impl<C> SendTransactionTypes<C> for Test where Call: From<C> {
+	type OverarchingCall = Call;
+	type Extrinsic = UncheckedExtrinsic;
+}
+
+impl<C> CreateSignedTransaction<C> for Test where Call: From<C> {
+	fn create_transaction<A: AppCrypto<Self::Public, Self::Signature>>(
+		call: Call,
+		_public: UintAuthorityId,
+		account: u64,
+		_nonce: u64,
+	) -> Option<(Call, <UncheckedExtrinsic as ExtrinsicT>::SignaturePayload)> {
+		Some((call, (account, (), ())))
+	}
+}
+
+impl pallet_oracle::Config for Test {
+	type Event = Event;
+	type AssetId = u32;
+	type AdminOrigin = frame_system::EnsureRoot<u64>;
+	type Decimals = MockDecimals;
+	type MaxFeeders = MaxFeeders;
+	type StalenessThreshold = StalenessThreshold;
+	type AuthorityId = TestAuthId;
+	type FetchInterval = FetchInterval;
+	type MaxPricesPerFeed = MaxPricesPerFeed;
+	type UnsignedPriority = UnsignedPriority;
+	type MaxTwapWindow = MaxTwapWindow;
+	type TwapWindow = TwapWindow;
+	type MaxPriceDeviation = MaxPriceDeviation;
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_oracle::GenesisConfig::<Test> {
+		feeders: vec![BOB, ALICE],
+	}.assimilate_storage(&mut t).unwrap();
+
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
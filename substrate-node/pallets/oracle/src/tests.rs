@@ -0,0 +1,278 @@
+use crate::{Error, PricePayload, PriceProvider, PriceSource, SOURCES_KEY, UNSIGNED_KEY, mock::*};
+use codec::{Decode, Encode};
+use frame_support::{assert_ok, assert_noop, traits::OffchainWorker};
+use sp_core::offchain::{testing, OffchainDbExt, OffchainWorkerExt, TransactionPoolExt};
+use sp_runtime::{
+	offchain::storage::StorageValueRef,
+	testing::{TestSignature, UintAuthorityId},
+	traits::ValidateUnsigned,
+	transaction_validity::{InvalidTransaction, TransactionSource},
+	DispatchError, FixedPointNumber, FixedU128,
+};
+
+fn price(asset_id: u32) -> Option<FixedU128> {
+	<Oracle as PriceProvider<u32, u64>>::price(asset_id)
+}
+
+#[test]
+fn only_authorized_feeders_submit_prices() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(Oracle::feeders(), vec![ALICE, BOB]);
+		assert_noop!(
+			Oracle::feed_price(Origin::signed(CHARLIE), DOT, FixedU128::from(10)),
+			Error::<Test>::NotFeeder
+		);
+		assert_noop!(Oracle::add_feeder(Origin::signed(ALICE), CHARLIE), DispatchError::BadOrigin);
+		assert_noop!(Oracle::add_feeder(Origin::root(), ALICE), Error::<Test>::AlreadyFeeder);
+		assert_ok!(Oracle::add_feeder(Origin::root(), CHARLIE));
+		assert_noop!(Oracle::add_feeder(Origin::root(), DAVE), Error::<Test>::TooManyFeeders);
+
+		assert_noop!(
+			Oracle::feed_price(Origin::signed(CHARLIE), DOT, FixedU128::from(0)),
+			Error::<Test>::ZeroPrice
+		);
+		assert_ok!(Oracle::feed_price(Origin::signed(CHARLIE), DOT, FixedU128::from(10)));
+		assert_eq!(price(DOT), Some(FixedU128::from(10)));
+
+		// Feeds of removed feeders are left out of the median
+		assert_ok!(Oracle::feed_price(Origin::signed(ALICE), DOT, FixedU128::from(20)));
+		assert_ok!(Oracle::remove_feeder(Origin::root(), CHARLIE));
+		assert_noop!(Oracle::remove_feeder(Origin::root(), CHARLIE), Error::<Test>::NotFeeder);
+		assert_ok!(Oracle::feed_price(Origin::signed(BOB), DOT, FixedU128::from(40)));
+		assert_eq!(price(DOT), Some(FixedU128::from(30)));
+	});
+}
+
+#[test]
+fn price_is_the_median_of_fresh_feeds() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Oracle::add_feeder(Origin::root(), CHARLIE));
+		assert_ok!(Oracle::feed_price(Origin::signed(ALICE), DOT, FixedU128::from(10)));
+		assert_ok!(Oracle::feed_price(Origin::signed(BOB), DOT, FixedU128::from(100)));
+		assert_eq!(price(DOT), Some(FixedU128::from(55)));
+
+		// An outlier moves the median by at most one feed
+		assert_ok!(Oracle::feed_price(Origin::signed(CHARLIE), DOT, FixedU128::from(1_000)));
+		assert_eq!(price(DOT), Some(FixedU128::from(100)));
+
+		// Feeds older than the staleness threshold are dropped
+		System::set_block_number(5);
+		assert_ok!(Oracle::feed_price(Origin::signed(CHARLIE), DOT, FixedU128::from(12)));
+		System::set_block_number(12);
+		assert_ok!(Oracle::feed_price(Origin::signed(BOB), DOT, FixedU128::from(14)));
+		assert_eq!(price(DOT), Some(FixedU128::from(13)));
+
+		// And so is the oracle price once nothing was fed for as long
+		System::set_block_number(22);
+		assert_eq!(price(DOT), Some(FixedU128::from(13)));
+		System::set_block_number(23);
+		assert_eq!(price(DOT), None);
+		assert_eq!(price(KSM), None);
+	});
+}
+
+#[test]
+fn values_account_for_asset_decimals() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Oracle::feed_price(Origin::signed(ALICE), DOT, FixedU128::from(5)));
+		assert_ok!(Oracle::feed_price(Origin::signed(ALICE), KSM, FixedU128::saturating_from_rational(5, 2)));
+
+		// 3 DOT at 5 are worth 15 and 2 KSM at 2.5 are worth 5
+		assert_eq!(Oracle::value(DOT, 30_000_000_000u64), Some(FixedU128::from(15)));
+		assert_eq!(Oracle::value(KSM, 2_000_000_000_000u64), Some(FixedU128::from(5)));
+		assert_eq!(<Oracle as PriceProvider<u32, u64>>::amount(KSM, FixedU128::from(15)), Some(6_000_000_000_000));
+		// Amounts that do not fit the balance type have no value rather than a saturated one
+		assert_eq!(<Oracle as PriceProvider<u32, u32>>::amount(KSM, FixedU128::from(15)), None);
+
+		KsmDecimals::set(0);
+		assert_eq!(<Oracle as PriceProvider<u32, u64>>::amount(KSM, FixedU128::from(15)), Some(6));
+		assert_eq!(<Oracle as PriceProvider<u32, u64>>::amount(KSM, FixedU128::from(14)), Some(5));
+	});
+}
+
+fn set_sources(sources: Vec<PriceSource<u32>>) {
+	StorageValueRef::persistent(SOURCES_KEY).set(&sources);
+}
+
+fn source(asset_id: u32, url: &str, path: &str) -> PriceSource<u32> {
+	PriceSource { asset_id, url: url.as_bytes().to_vec(), path: path.as_bytes().to_vec() }
+}
+
+fn expect_get(offchain: &testing::TestOffchainExt, uri: &str, response: &str) {
+	offchain.0.write().expect_request(testing::PendingRequest {
+		method: "GET".into(),
+		uri: uri.into(),
+		response: Some(response.as_bytes().to_vec()),
+		sent: true,
+		..Default::default()
+	});
+}
+
+#[test]
+fn twap_averages_prices_over_the_window() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Oracle::feed_price(Origin::signed(ALICE), DOT, FixedU128::from(10)));
+		assert_eq!(Oracle::twap(DOT, 0), Some(FixedU128::from(10)));
+		assert_eq!(Oracle::twap(DOT, 4), None);
+
+		System::set_block_number(3);
+		assert_ok!(Oracle::feed_price(Origin::signed(ALICE), DOT, FixedU128::from(11)));
+		System::set_block_number(6);
+		assert_ok!(Oracle::feed_price(Origin::signed(ALICE), DOT, FixedU128::from(12)));
+
+		// 10 for 2 blocks and 11 for 3 blocks since block 1
+		assert_eq!(Oracle::twap(DOT, 4), Some(FixedU128::saturating_from_rational(53, 5)));
+		assert_eq!(<Oracle as PriceProvider<u32, u64>>::twap(DOT), Some(FixedU128::saturating_from_rational(53, 5)));
+		// And 12 for the 2 blocks since
+		System::set_block_number(8);
+		assert_eq!(Oracle::twap(DOT, 1), Some(FixedU128::from(12)));
+		assert_eq!(Oracle::twap(DOT, 7), Some(FixedU128::saturating_from_rational(77, 7)));
+		assert_eq!(Oracle::twap(DOT, 8), None);
+
+		// Observations older than the longest window are pruned
+		System::set_block_number(16);
+		assert_ok!(Oracle::feed_price(Origin::signed(ALICE), DOT, FixedU128::from(12)));
+		assert_eq!(Oracle::observations(DOT).iter().map(|observation| observation.block).collect::<Vec<_>>(), vec![6, 16]);
+		assert_eq!(Oracle::twap(DOT, 10), Some(FixedU128::from(12)));
+	});
+}
+
+#[test]
+fn abrupt_price_moves_trip_the_circuit_breaker() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Oracle::feed_price(Origin::signed(ALICE), DOT, FixedU128::from(10)));
+		System::set_block_number(3);
+		assert_ok!(Oracle::feed_price(Origin::signed(ALICE), DOT, FixedU128::from(11)));
+		System::set_block_number(6);
+		assert_ok!(Oracle::feed_price(Origin::signed(ALICE), DOT, FixedU128::from(12)));
+		assert_eq!(Oracle::circuit_breaker(DOT), None);
+
+		// 20 is 75% above the average of 11.4 since block 3
+		System::set_block_number(8);
+		assert_ok!(Oracle::feed_price(Origin::signed(ALICE), DOT, FixedU128::from(20)));
+		assert_eq!(Oracle::circuit_breaker(DOT), Some(8));
+		assert!(System::events().iter().any(|record| record.event == Event::pallet_oracle(
+			crate::Event::CircuitBreakerTripped(DOT, FixedU128::from(20), FixedU128::saturating_from_rational(57, 5))
+		)));
+		assert!(<Oracle as PriceProvider<u32, u64>>::is_paused(DOT));
+		assert!(!<Oracle as PriceProvider<u32, u64>>::is_paused(KSM));
+
+		// The spot price keeps updating while the asset is paused
+		assert_eq!(price(DOT), Some(FixedU128::from(20)));
+
+		assert_noop!(Oracle::reset_circuit_breaker(Origin::signed(ALICE), DOT), DispatchError::BadOrigin);
+		assert_noop!(Oracle::reset_circuit_breaker(Origin::root(), KSM), Error::<Test>::CircuitBreakerNotTripped);
+		assert_ok!(Oracle::reset_circuit_breaker(Origin::root(), DOT));
+		assert!(!<Oracle as PriceProvider<u32, u64>>::is_paused(DOT));
+	});
+}
+
+#[test]
+fn json_prices_are_found_by_path() {
+	new_test_ext().execute_with(|| {
+		let body = r#"{"data":[{"symbol":"DOT","price":"12.5"},{"symbol":"KSM","price":2.5e2}]}"#;
+		assert_eq!(Oracle::parse_price(body, b"data.0.price"), Some(FixedU128::saturating_from_rational(25, 2)));
+		assert_eq!(Oracle::parse_price(body, b"data.1.price"), Some(FixedU128::from(250)));
+		assert_eq!(Oracle::parse_price(r#"{"USD":0.05}"#, b"USD"), Some(FixedU128::saturating_from_rational(1, 20)));
+		assert_eq!(Oracle::parse_price(r#"7"#, b""), Some(FixedU128::from(7)));
+
+		assert_eq!(Oracle::parse_price(body, b"data.2.price"), None);
+		assert_eq!(Oracle::parse_price(body, b"data.0.symbol"), None);
+		assert_eq!(Oracle::parse_price(r#"{"USD":-1}"#, b"USD"), None);
+		assert_eq!(Oracle::parse_price("not json", b""), None);
+	});
+}
+
+#[test]
+fn offchain_worker_submits_signed_prices_once_per_interval() {
+	let mut ext = new_test_ext();
+	let (offchain, _) = testing::TestOffchainExt::new();
+	let (pool, pool_state) = testing::TestTransactionPoolExt::new();
+	ext.register_extension(OffchainDbExt::new(offchain.clone()));
+	ext.register_extension(OffchainWorkerExt::new(offchain.clone()));
+	ext.register_extension(TransactionPoolExt::new(pool));
+	UintAuthorityId::set_all_keys(vec![ALICE]);
+	ext.execute_with(|| {
+		// Nothing is fetched until sources are configured
+		Oracle::offchain_worker(1);
+		assert!(pool_state.read().transactions.is_empty());
+
+		set_sources(vec![
+			source(DOT, "http://localhost:8000/dot", "price"),
+			source(KSM, "http://localhost:8000/ksm", "price"),
+		]);
+		expect_get(&offchain, "http://localhost:8000/dot", r#"{"price":"12.5"}"#);
+		expect_get(&offchain, "http://localhost:8000/ksm", r#"{"error":"unknown asset"}"#);
+		Oracle::offchain_worker(2);
+
+		// Sources failing to answer a price are skipped
+		let tx = pool_state.write().transactions.pop().unwrap();
+		assert!(pool_state.read().transactions.is_empty());
+		let tx = UncheckedExtrinsic::decode(&mut &*tx).unwrap();
+		assert_eq!(tx.signature.unwrap().0, ALICE);
+		assert_eq!(tx.function, Call::Oracle(crate::Call::feed_price(DOT, FixedU128::saturating_from_rational(25, 2))));
+
+		Oracle::offchain_worker(6);
+		assert!(pool_state.read().transactions.is_empty());
+		expect_get(&offchain, "http://localhost:8000/dot", r#"{"price":13}"#);
+		expect_get(&offchain, "http://localhost:8000/ksm", r#"{"price":2}"#);
+		Oracle::offchain_worker(7);
+		assert_eq!(pool_state.read().transactions.len(), 2);
+	});
+}
+
+#[test]
+fn unsigned_prices_need_a_fresh_signed_payload_of_a_feeder() {
+	let mut ext = new_test_ext();
+	let (offchain, _) = testing::TestOffchainExt::new();
+	let (pool, pool_state) = testing::TestTransactionPoolExt::new();
+	ext.register_extension(OffchainDbExt::new(offchain.clone()));
+	ext.register_extension(OffchainWorkerExt::new(offchain.clone()));
+	ext.register_extension(TransactionPoolExt::new(pool));
+	UintAuthorityId::set_all_keys(vec![ALICE]);
+	ext.execute_with(|| {
+		set_sources(vec![source(DOT, "http://localhost:8000/dot", "USD")]);
+		StorageValueRef::persistent(UNSIGNED_KEY).set(&true);
+		expect_get(&offchain, "http://localhost:8000/dot", r#"{"USD":12}"#);
+		Oracle::offchain_worker(1);
+
+		let tx = pool_state.write().transactions.pop().unwrap();
+		let tx = UncheckedExtrinsic::decode(&mut &*tx).unwrap();
+		assert_eq!(tx.signature, None);
+		let (payload, signature) = match tx.function {
+			Call::Oracle(crate::Call::feed_prices_unsigned(payload, signature)) => (payload, signature),
+			call => panic!("unexpected call {:?}", call),
+		};
+		assert_eq!(
+			payload,
+			PricePayload { public: UintAuthorityId(ALICE), block_number: 1, prices: vec![(DOT, FixedU128::from(12))] }
+		);
+		let validate = |payload: &PricePayload<UintAuthorityId, u32, u64>, signature: &TestSignature| {
+			Oracle::validate_unsigned(
+				TransactionSource::External,
+				&crate::Call::feed_prices_unsigned(payload.clone(), signature.clone()),
+			)
+		};
+		assert!(validate(&payload, &signature).is_ok());
+
+		// Payloads must be signed by the key of a feeder
+		let mut tampered = payload.clone();
+		tampered.prices = vec![(DOT, FixedU128::from(1))];
+		assert_eq!(validate(&tampered, &signature), InvalidTransaction::BadProof.into());
+		let stranger = PricePayload { public: UintAuthorityId(CHARLIE), ..payload.clone() };
+		let stranger_signature = TestSignature(CHARLIE, stranger.encode());
+		assert_eq!(validate(&stranger, &stranger_signature), InvalidTransaction::BadSigner.into());
+
+		// Each feeder submits at most once per interval
+		assert_ok!(Oracle::feed_prices_unsigned(Origin::none(), payload.clone(), signature.clone()));
+		assert_eq!(Oracle::aggregated_price(DOT).map(|feed| feed.price), Some(FixedU128::from(12)));
+		assert_eq!(Oracle::next_unsigned_at(ALICE), 6);
+		assert_eq!(validate(&payload, &signature), InvalidTransaction::Stale.into());
+
+		let later = PricePayload { block_number: 6, ..payload.clone() };
+		let later_signature = TestSignature(ALICE, later.encode());
+		assert_eq!(validate(&later, &later_signature), InvalidTransaction::Future.into());
+		System::set_block_number(6);
+		assert!(validate(&later, &later_signature).is_ok());
+	});
+}
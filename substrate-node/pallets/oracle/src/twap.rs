@@ -0,0 +1,80 @@
+//! Time weighted average prices and the circuit breaker built on them.
+//!
+//! Every oracle price update records the cumulative price, the sum of the price in force over
+//! every block so far. The average over a window is the growth of the cumulative price across it
+//! divided by its length. Observations older than `MaxTwapWindow` are pruned.
+
+use super::*;
+use frame_support::traits::Get;
+
+/// Cumulative price of an asset at a block.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Default, RuntimeDebug)]
+pub struct Observation<BlockNumber> {
+    pub block: BlockNumber,
+    /// Sum of the oracle price over every block up to `block`
+    pub cumulative: FixedU128,
+}
+
+impl<T: Config> Pallet<T> {
+    /// Time weighted average price of `asset_id` over at least the last `window` blocks.
+    ///
+    /// The average starts at the newest observation at or before the start of the window, and is
+    /// `None` while the history of the asset is shorter than the window or its price is stale.
+    pub fn twap(asset_id: T::AssetId, window: T::BlockNumber) -> Option<FixedU128> {
+        let spot = Prices::<T>::get(asset_id).filter(|feed| Self::is_fresh(feed))?;
+        if window.is_zero() {
+            return Some(spot.price);
+        }
+        let now = <frame_system::Pallet<T>>::block_number();
+        let observations = Observations::<T>::get(asset_id);
+        let cumulative = Self::accumulate(observations.last()?, spot.price, now);
+
+        let start = now.checked_sub(&window)?;
+        let first = observations.iter().rev().find(|observation| observation.block <= start)?;
+        let blocks = now.saturating_sub(first.block).saturated_into::<u128>();
+        cumulative
+            .saturating_sub(first.cumulative)
+            .checked_div(&FixedU128::saturating_from_integer(blocks))
+    }
+
+    /// Record the cumulative price up to `now`, before the price of `asset_id` is updated.
+    pub(crate) fn observe(asset_id: T::AssetId, now: T::BlockNumber) {
+        let previous = Prices::<T>::get(asset_id);
+        Observations::<T>::mutate(asset_id, |observations| {
+            let cumulative = match (observations.last(), previous) {
+                (Some(last), _) if last.block == now => return,
+                (Some(last), Some(previous)) => Self::accumulate(last, previous.price, now),
+                _ => Zero::zero(),
+            };
+            observations.push(Observation { block: now, cumulative });
+
+            // Keep the newest observation at or before the start of the longest window
+            let horizon = now.saturating_sub(T::MaxTwapWindow::get());
+            let oldest = observations.iter().rposition(|observation| observation.block <= horizon).unwrap_or(0);
+            observations.drain(..oldest);
+        });
+    }
+
+    /// Trip the circuit breaker of `asset_id` if `spot` deviates too far from its average.
+    pub(crate) fn check_circuit_breaker(asset_id: T::AssetId, spot: FixedU128, now: T::BlockNumber) {
+        if CircuitBreakers::<T>::contains_key(asset_id) {
+            return;
+        }
+        let twap = match Self::twap(asset_id, T::TwapWindow::get()) {
+            Some(twap) if twap > Zero::zero() => twap,
+            _ => return,
+        };
+        let difference = if spot > twap { spot.saturating_sub(twap) } else { twap.saturating_sub(spot) };
+        let deviation = difference.checked_div(&twap).unwrap_or_else(Bounded::max_value);
+        if deviation > FixedU128::from(T::MaxPriceDeviation::get()) {
+            CircuitBreakers::<T>::insert(asset_id, now);
+            Self::deposit_event(Event::CircuitBreakerTripped(asset_id, spot, twap));
+        }
+    }
+
+    /// Cumulative price at `now`, with `price` in force since `last`.
+    fn accumulate(last: &Observation<T::BlockNumber>, price: FixedU128, now: T::BlockNumber) -> FixedU128 {
+        let blocks = now.saturating_sub(last.block).saturated_into::<u128>();
+        last.cumulative.saturating_add(price.saturating_mul(FixedU128::saturating_from_integer(blocks)))
+    }
+}
@@ -0,0 +1,126 @@
+//! RPC interface for the vault pallet.
+
+use std::sync::Arc;
+
+use codec::Codec;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT, FixedU128};
+
+pub use pallet_vault_runtime_api::VaultApi as VaultRuntimeApi;
+
+#[rpc]
+pub trait VaultApi<BlockHash, AssetId, AccountId, Balance> {
+    #[rpc(name = "vault_exchangeRate")]
+    fn exchange_rate(&self, asset_id: AssetId, at: Option<BlockHash>) -> Result<FixedU128>;
+
+    #[rpc(name = "vault_totalCash")]
+    fn total_cash(&self, asset_id: AssetId, at: Option<BlockHash>) -> Result<Balance>;
+
+    #[rpc(name = "vault_totalBorrows")]
+    fn total_borrows(&self, asset_id: AssetId, at: Option<BlockHash>) -> Result<Balance>;
+
+    #[rpc(name = "vault_utilization")]
+    fn utilization(&self, asset_id: AssetId, at: Option<BlockHash>) -> Result<FixedU128>;
+
+    #[rpc(name = "vault_underlyingBalance")]
+    fn underlying_balance(
+        &self,
+        asset_id: AssetId,
+        who: AccountId,
+        at: Option<BlockHash>,
+    ) -> Result<Balance>;
+
+    #[rpc(name = "vault_quoteDeposit")]
+    fn quote_deposit(&self, asset_id: AssetId, amount: Balance, at: Option<BlockHash>) -> Result<Balance>;
+
+    #[rpc(name = "vault_quoteWithdraw")]
+    fn quote_withdraw(&self, asset_id: AssetId, amount: Balance, at: Option<BlockHash>) -> Result<Balance>;
+}
+
+/// Error code returned when the runtime API call fails.
+const RUNTIME_ERROR: i64 = 1;
+
+/// Implements the vault RPC methods by calling into the runtime.
+pub struct Vault<C, B> {
+    client: Arc<C>,
+    _marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> Vault<C, B> {
+    /// Create new `Vault` with the given reference to the client.
+    pub fn new(client: Arc<C>) -> Self {
+        Self { client, _marker: Default::default() }
+    }
+}
+
+fn runtime_error(e: impl std::fmt::Debug) -> RpcError {
+    RpcError {
+        code: ErrorCode::ServerError(RUNTIME_ERROR),
+        message: "Unable to query the vault.".into(),
+        data: Some(format!("{:?}", e).into()),
+    }
+}
+
+impl<C, Block, AssetId, AccountId, Balance> VaultApi<<Block as BlockT>::Hash, AssetId, AccountId, Balance>
+    for Vault<C, Block>
+where
+    Block: BlockT,
+    C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+    C::Api: VaultRuntimeApi<Block, AssetId, AccountId, Balance>,
+    AssetId: Codec,
+    AccountId: Codec,
+    Balance: Codec,
+{
+    fn exchange_rate(&self, asset_id: AssetId, at: Option<<Block as BlockT>::Hash>) -> Result<FixedU128> {
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        self.client.runtime_api().exchange_rate(&at, asset_id).map_err(runtime_error)
+    }
+
+    fn total_cash(&self, asset_id: AssetId, at: Option<<Block as BlockT>::Hash>) -> Result<Balance> {
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        self.client.runtime_api().total_cash(&at, asset_id).map_err(runtime_error)
+    }
+
+    fn total_borrows(&self, asset_id: AssetId, at: Option<<Block as BlockT>::Hash>) -> Result<Balance> {
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        self.client.runtime_api().total_borrows(&at, asset_id).map_err(runtime_error)
+    }
+
+    fn utilization(&self, asset_id: AssetId, at: Option<<Block as BlockT>::Hash>) -> Result<FixedU128> {
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        self.client.runtime_api().utilization(&at, asset_id).map_err(runtime_error)
+    }
+
+    fn underlying_balance(
+        &self,
+        asset_id: AssetId,
+        who: AccountId,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Balance> {
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        self.client.runtime_api().underlying_balance(&at, asset_id, who).map_err(runtime_error)
+    }
+
+    fn quote_deposit(
+        &self,
+        asset_id: AssetId,
+        amount: Balance,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Balance> {
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        self.client.runtime_api().quote_deposit(&at, asset_id, amount).map_err(runtime_error)
+    }
+
+    fn quote_withdraw(
+        &self,
+        asset_id: AssetId,
+        amount: Balance,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Balance> {
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        self.client.runtime_api().quote_withdraw(&at, asset_id, amount).map_err(runtime_error)
+    }
+}
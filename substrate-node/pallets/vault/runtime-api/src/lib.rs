@@ -0,0 +1,28 @@
+//! Runtime API definition for the vault pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+use codec::Codec;
+use sp_runtime::FixedU128;
+
+sp_api::decl_runtime_apis! {
+    pub trait VaultApi<AssetId, AccountId, Balance> where
+        AssetId: Codec,
+        AccountId: Codec,
+        Balance: Codec,
+    {
+        /// Underlying redeemable per rToken.
+        fn exchange_rate(asset_id: AssetId) -> FixedU128;
+        /// Underlying held by the vault.
+        fn total_cash(asset_id: AssetId) -> Balance;
+        /// Underlying lent out, including accrued interest.
+        fn total_borrows(asset_id: AssetId) -> Balance;
+        /// Share of the underlying that is lent out.
+        fn utilization(asset_id: AssetId) -> FixedU128;
+        /// Underlying redeemable for the rTokens held by `who`.
+        fn underlying_balance(asset_id: AssetId, who: AccountId) -> Balance;
+        /// rTokens minted for depositing `amount` of underlying.
+        fn quote_deposit(asset_id: AssetId, amount: Balance) -> Balance;
+        /// Underlying paid out for burning `amount` of rTokens.
+        fn quote_withdraw(asset_id: AssetId, amount: Balance) -> Balance;
+    }
+}
@@ -0,0 +1,126 @@
+use frame_support::traits::{
+    tokens::{fungible, fungibles, DepositConsequence, WithdrawConsequence},
+    Get,
+};
+use sp_runtime::{DispatchError, DispatchResult};
+use sp_std::marker::PhantomData;
+
+/// Multi-asset currency exposing the native currency `Native` as asset `NativeAssetId` next to
+/// the assets of `Assets`.
+///
+/// Both share one balance type, so native balances are never truncated. The reserved id must not
+/// be used by `Assets`, whose asset of that id is unreachable.
+pub struct NativeAndAssets<Native, Assets, NativeAssetId>(PhantomData<(Native, Assets, NativeAssetId)>);
+
+impl<AccountId, Native, Assets, NativeAssetId> fungibles::Inspect<AccountId>
+    for NativeAndAssets<Native, Assets, NativeAssetId>
+where
+    Native: fungible::Inspect<AccountId, Balance = Assets::Balance>,
+    Assets: fungibles::Inspect<AccountId>,
+    NativeAssetId: Get<Assets::AssetId>,
+{
+    type AssetId = Assets::AssetId;
+    type Balance = Assets::Balance;
+
+    fn total_issuance(asset: Self::AssetId) -> Self::Balance {
+        if asset == NativeAssetId::get() {
+            Native::total_issuance()
+        } else {
+            Assets::total_issuance(asset)
+        }
+    }
+
+    fn minimum_balance(asset: Self::AssetId) -> Self::Balance {
+        if asset == NativeAssetId::get() {
+            Native::minimum_balance()
+        } else {
+            Assets::minimum_balance(asset)
+        }
+    }
+
+    fn balance(asset: Self::AssetId, who: &AccountId) -> Self::Balance {
+        if asset == NativeAssetId::get() {
+            Native::balance(who)
+        } else {
+            Assets::balance(asset, who)
+        }
+    }
+
+    fn reducible_balance(asset: Self::AssetId, who: &AccountId, keep_alive: bool) -> Self::Balance {
+        if asset == NativeAssetId::get() {
+            Native::reducible_balance(who, keep_alive)
+        } else {
+            Assets::reducible_balance(asset, who, keep_alive)
+        }
+    }
+
+    fn can_deposit(asset: Self::AssetId, who: &AccountId, amount: Self::Balance) -> DepositConsequence {
+        if asset == NativeAssetId::get() {
+            Native::can_deposit(who, amount)
+        } else {
+            Assets::can_deposit(asset, who, amount)
+        }
+    }
+
+    fn can_withdraw(
+        asset: Self::AssetId,
+        who: &AccountId,
+        amount: Self::Balance,
+    ) -> WithdrawConsequence<Self::Balance> {
+        if asset == NativeAssetId::get() {
+            Native::can_withdraw(who, amount)
+        } else {
+            Assets::can_withdraw(asset, who, amount)
+        }
+    }
+}
+
+impl<AccountId, Native, Assets, NativeAssetId> fungibles::Mutate<AccountId>
+    for NativeAndAssets<Native, Assets, NativeAssetId>
+where
+    Native: fungible::Mutate<AccountId, Balance = Assets::Balance>,
+    Assets: fungibles::Mutate<AccountId>,
+    NativeAssetId: Get<Assets::AssetId>,
+{
+    fn mint_into(asset: Self::AssetId, who: &AccountId, amount: Self::Balance) -> DispatchResult {
+        if asset == NativeAssetId::get() {
+            Native::mint_into(who, amount)
+        } else {
+            Assets::mint_into(asset, who, amount)
+        }
+    }
+
+    fn burn_from(
+        asset: Self::AssetId,
+        who: &AccountId,
+        amount: Self::Balance,
+    ) -> Result<Self::Balance, DispatchError> {
+        if asset == NativeAssetId::get() {
+            Native::burn_from(who, amount)
+        } else {
+            Assets::burn_from(asset, who, amount)
+        }
+    }
+}
+
+impl<AccountId, Native, Assets, NativeAssetId> fungibles::Transfer<AccountId>
+    for NativeAndAssets<Native, Assets, NativeAssetId>
+where
+    Native: fungible::Transfer<AccountId, Balance = Assets::Balance>,
+    Assets: fungibles::Transfer<AccountId>,
+    NativeAssetId: Get<Assets::AssetId>,
+{
+    fn transfer(
+        asset: Self::AssetId,
+        source: &AccountId,
+        dest: &AccountId,
+        amount: Self::Balance,
+        keep_alive: bool,
+    ) -> Result<Self::Balance, DispatchError> {
+        if asset == NativeAssetId::get() {
+            Native::transfer(source, dest, amount, keep_alive)
+        } else {
+            Assets::transfer(asset, source, dest, amount, keep_alive)
+        }
+    }
+}
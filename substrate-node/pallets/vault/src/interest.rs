@@ -0,0 +1,53 @@
+use codec::{Decode, Encode};
+use sp_runtime::{
+    traits::{One, Saturating, Zero},
+    FixedPointNumber, FixedU128, RuntimeDebug,
+};
+
+/// Kinked utilization curve used to price vault borrows.
+///
+/// All rates are expressed per block.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, Default, RuntimeDebug)]
+pub struct InterestRateModel {
+    /// Borrow rate at zero utilization
+    pub base_rate: FixedU128,
+    /// Rate added per unit of utilization below the kink
+    pub slope1: FixedU128,
+    /// Utilization at which the curve switches to `slope2`
+    pub kink: FixedU128,
+    /// Rate added per unit of utilization above the kink
+    pub slope2: FixedU128,
+}
+
+impl InterestRateModel {
+    /// A model is valid when its kink lies within `[0, 1]`.
+    pub fn is_valid(&self) -> bool {
+        self.kink <= FixedU128::one()
+    }
+
+    /// Share of the underlying that is currently lent out: `borrows / (cash + borrows)`.
+    pub fn utilization(cash: u128, borrows: u128) -> FixedU128 {
+        FixedU128::checked_from_rational(borrows, cash.saturating_add(borrows))
+            .unwrap_or_else(Zero::zero)
+    }
+
+    /// Per block borrow rate for the given utilization.
+    pub fn borrow_rate(&self, utilization: FixedU128) -> FixedU128 {
+        if utilization <= self.kink {
+            self.base_rate.saturating_add(self.slope1.saturating_mul(utilization))
+        } else {
+            self.base_rate
+                .saturating_add(self.slope1.saturating_mul(self.kink))
+                .saturating_add(self.slope2.saturating_mul(utilization.saturating_sub(self.kink)))
+        }
+    }
+}
+
+/// Debt of a single borrower, stored relative to the borrow index at the time it last changed.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, Default, RuntimeDebug)]
+pub struct BorrowSnapshot<Balance> {
+    /// Debt including the interest accrued up to `interest_index`
+    pub principal: Balance,
+    /// Borrow index when `principal` was last updated
+    pub interest_index: FixedU128,
+}
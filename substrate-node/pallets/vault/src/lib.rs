@@ -1,57 +1,320 @@
 #![cfg_attr(not(feature = "std"), no_std)]
-use sp_std::{fmt::Debug};
-use frame_support::{decl_error, decl_event, decl_module, decl_storage, ensure, traits::{Get, Currency}, Parameter, PalletId};
-use frame_system::{ensure_signed};
-use codec::{HasCompact};
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+mod interest;
+pub use interest::{InterestRateModel, BorrowSnapshot};
+
+mod adapter;
+pub use adapter::NativeAndAssets;
+
+use sp_std::prelude::*;
+use sp_std::convert::TryInto;
+use frame_support::{ensure, transactional, dispatch::DispatchResult, weights::Weight};
+use codec::{Encode, Decode};
 use sp_runtime::{
 	traits::{
-		Zero, AccountIdConversion, AtLeast32BitUnsigned, Member, StaticLookup, LookupError
-	}, MultiAddress
+		Zero, One, Saturating, CheckedAdd, CheckedDiv, AccountIdConversion,
+	}, FixedPointNumber, FixedU128, Permill, RuntimeDebug, SaturatedConversion
 };
-use frame_support::traits::tokens::fungibles::{Inspect, Mutate, Transfer};
+use pallet_currencies::MultiCurrency;
+
+/// Creates the rToken of an asset on behalf of the vault.
+pub trait RTokenFactory<AccountId, AssetId, Balance> {
+    /// Create asset `id` owned by `owner`.
+    fn create(id: AssetId, owner: &AccountId, min_balance: Balance) -> DispatchResult;
+
+    /// Name, symbol and decimals of an existing asset.
+    fn metadata(id: AssetId) -> (Vec<u8>, Vec<u8>, u8);
+
+    /// Set the metadata of an asset owned by `owner`.
+    fn set_metadata(id: AssetId, owner: &AccountId, name: Vec<u8>, symbol: Vec<u8>, decimals: u8) -> DispatchResult;
+
+    /// Owner of an existing asset, the only account able to mint it.
+    fn owner(id: AssetId) -> Option<AccountId>;
+}
+
+impl<AccountId, AssetId, Balance> RTokenFactory<AccountId, AssetId, Balance> for () {
+    fn create(_: AssetId, _: &AccountId, _: Balance) -> DispatchResult {
+        Err("rToken creation is not supported".into())
+    }
 
-type BalanceOf<T> = <<T as Config>::Currencies as Inspect<<T as frame_system::Config>::AccountId>>::Balance;
-type AssetId<T> = <<T as Config>::Currencies as Inspect<<T as frame_system::Config>::AccountId>>::AssetId;
+    fn metadata(_: AssetId) -> (Vec<u8>, Vec<u8>, u8) {
+        (Vec::new(), Vec::new(), 0)
+    }
 
-pub trait Config: frame_system::Config {
-    type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
+    fn set_metadata(_: AssetId, _: &AccountId, _: Vec<u8>, _: Vec<u8>, _: u8) -> DispatchResult {
+        Err("rToken creation is not supported".into())
+    }
 
-    // type Balance: Member + Parameter + AtLeast32BitUnsigned + Default + Copy;
+    fn owner(_: AssetId) -> Option<AccountId> {
+        None
+    }
+}
 
-    // type AssetId: Member + Parameter + Default + Copy + HasCompact;
+/// Operator controlled limits of a vault asset.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Default, RuntimeDebug)]
+pub struct VaultAssetConfig<Balance> {
+    /// Maximum underlying held for rToken holders, uncapped if `None`
+    pub deposit_cap: Option<Balance>,
+    /// Smallest amount accepted by `vault_deposit`
+    pub min_deposit: Balance,
+    /// Maximum outstanding borrows, uncapped if `None`
+    pub borrow_cap: Option<Balance>,
+    /// Share of accrued interest set aside as reserves
+    pub reserve_factor: Permill,
+    /// Whether `vault_deposit` is paused
+    pub deposit_paused: bool,
+    /// Whether `vault_withdraw` is paused
+    pub withdraw_paused: bool,
+    /// Whether `borrow` is paused
+    pub borrow_paused: bool,
+}
 
-    /// The vault's module id, used for deriving its sovereign account ID.
-    type PalletId: Get<PalletId>;
+/// Withdrawal waiting for vault liquidity, with its rTokens held by the vault.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Default, RuntimeDebug)]
+pub struct WithdrawalRequest<AccountId, Balance> {
+    /// Account receiving the underlying
+    pub who: AccountId,
+    /// rTokens still to be redeemed
+    pub rtokens: Balance,
+}
 
-    type LiquidatorPalletId: Get<PalletId>;
+/// Vault operations that can be paused independently.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum VaultOperation {
+    Deposit,
+    Withdraw,
+    Borrow,
+}
 
-    type Currencies: Inspect<Self::AccountId> + Mutate<Self::AccountId> + Transfer<Self::AccountId>;
+/// Storage layout versions of the vault.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum Releases {
+    /// `decl_storage!` layout under the `Vault` prefix
+    V1_0_0,
+    /// FRAME v2 layout under the runtime pallet name
+    V2_0_0,
 }
 
-// 3. Storage
-decl_storage! {
-    trait Store for Module<T: Config> as Vault {
-        /// RTokens are minted based on the original asset
-        RTokens: map hasher(blake2_128_concat) AssetId<T> => AssetId<T>;
+impl Default for Releases {
+    fn default() -> Self {
+        Releases::V1_0_0
     }
 }
 
-// 4. Events
-decl_event! {
-    pub enum Event<T> where 
-        AccountId = <T as frame_system::Config>::AccountId ,
-        Balance = BalanceOf<T>,
-    {
+#[frame_support::pallet]
+pub mod pallet {
+    use super::*;
+    use frame_support::{
+        pallet_prelude::*,
+        traits::{EnsureOrigin, PalletInfo as _},
+        weights::GetDispatchInfo,
+        PalletId,
+    };
+    use frame_system::pallet_prelude::*;
+    use codec::HasCompact;
+    use sp_runtime::traits::{AtLeast32BitUnsigned, Dispatchable, MaybeSerializeDeserialize, Member};
+
+    #[pallet::config]
+    pub trait Config: frame_system::Config {
+        type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+        type Balance: Member + Parameter + AtLeast32BitUnsigned + Default + Copy + MaybeSerializeDeserialize;
+
+        type AssetId: Member + Parameter + Default + Copy + HasCompact + MaybeSerializeDeserialize;
+
+        /// The vault's module id, used for deriving its sovereign account ID.
+        #[pallet::constant]
+        type PalletId: Get<PalletId>;
+
+        #[pallet::constant]
+        type LiquidatorPalletId: Get<PalletId>;
+
+        type Currencies: MultiCurrency<Self::AccountId, AssetId = Self::AssetId, Balance = Self::Balance>;
+
+        /// Origin allowed to create and register rTokens.
+        type RegisterOrigin: EnsureOrigin<Self::Origin>;
+
+        /// Creates rTokens owned by the vault.
+        type RTokenFactory: RTokenFactory<Self::AccountId, Self::AssetId, Self::Balance>;
+
+        /// Origin allowed to configure vault assets.
+        type AdminOrigin: EnsureOrigin<Self::Origin>;
+
+        /// The call dispatched by a flash loan borrower.
+        type Call: Parameter + Dispatchable<Origin = Self::Origin> + GetDispatchInfo;
+
+        /// Fee charged on flash loans, paid to rToken holders.
+        #[pallet::constant]
+        type FlashLoanFee: Get<Permill>;
+
+        /// Maximum number of pending withdrawal requests per asset.
+        #[pallet::constant]
+        type MaxWithdrawalRequests: Get<u32>;
+
+        /// Weight budget for filling queued withdrawals in `on_initialize`.
+        type WithdrawalQueueWeight: Get<Weight>;
+    }
+
+    #[pallet::pallet]
+    #[pallet::generate_store(pub(super) trait Store)]
+    pub struct Pallet<T>(_);
+
+    /// RTokens are minted based on the original asset
+    #[pallet::storage]
+    pub(super) type RTokens<T: Config> = StorageMap<_, Blake2_128Concat, T::AssetId, T::AssetId, ValueQuery>;
+
+    /// Outstanding debt of a borrower for a given asset
+    #[pallet::storage]
+    #[pallet::getter(fn borrows)]
+    pub type Borrows<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat, T::AssetId,
+        Blake2_128Concat, T::AccountId,
+        BorrowSnapshot<T::Balance>,
+        ValueQuery,
+    >;
+
+    /// Sum of all outstanding debt for a given asset, including accrued interest
+    #[pallet::storage]
+    #[pallet::getter(fn total_borrows)]
+    pub type TotalBorrows<T: Config> = StorageMap<_, Blake2_128Concat, T::AssetId, T::Balance, ValueQuery>;
+
+    /// Portion of the underlying of an asset owned by the protocol rather than rToken holders
+    #[pallet::storage]
+    #[pallet::getter(fn total_reserves)]
+    pub type TotalReserves<T: Config> = StorageMap<_, Blake2_128Concat, T::AssetId, T::Balance, ValueQuery>;
+
+    /// Caps, minimums and pause flags of an asset
+    #[pallet::storage]
+    #[pallet::getter(fn asset_config)]
+    pub type AssetConfigs<T: Config> = StorageMap<_, Blake2_128Concat, T::AssetId, VaultAssetConfig<T::Balance>, ValueQuery>;
+
+    /// Identifier of the next withdrawal request
+    #[pallet::storage]
+    #[pallet::getter(fn next_withdrawal_id)]
+    pub(super) type NextWithdrawalId<T> = StorageValue<_, u64, ValueQuery>;
+
+    /// Pending withdrawal requests of an asset
+    #[pallet::storage]
+    #[pallet::getter(fn withdrawal_requests)]
+    pub type WithdrawalRequests<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat, T::AssetId,
+        Twox64Concat, u64,
+        WithdrawalRequest<T::AccountId, T::Balance>,
+    >;
+
+    /// Pending withdrawal request ids of an asset, oldest first
+    #[pallet::storage]
+    #[pallet::getter(fn withdrawal_queue)]
+    pub type WithdrawalQueue<T: Config> = StorageMap<_, Blake2_128Concat, T::AssetId, Vec<u64>, ValueQuery>;
+
+    /// Set while a flash loan is dispatching its inner call
+    #[pallet::storage]
+    pub(super) type FlashLoanActive<T> = StorageValue<_, bool, ValueQuery>;
+
+    /// Interest rate model of a registered asset
+    #[pallet::storage]
+    #[pallet::getter(fn rate_models)]
+    pub type RateModels<T: Config> = StorageMap<_, Blake2_128Concat, T::AssetId, InterestRateModel>;
+
+    /// Accumulated borrow interest of an asset, starting at one
+    #[pallet::storage]
+    pub type BorrowIndex<T: Config> = StorageMap<_, Blake2_128Concat, T::AssetId, FixedU128>;
+
+    /// Block at which interest was last accrued for an asset
+    #[pallet::storage]
+    #[pallet::getter(fn accrual_block)]
+    pub type AccrualBlock<T: Config> = StorageMap<_, Blake2_128Concat, T::AssetId, T::BlockNumber, ValueQuery>;
+
+    /// Written off debt of an asset not covered by reserves, still counted in `TotalBorrows` until socialized
+    #[pallet::storage]
+    #[pallet::getter(fn bad_debt)]
+    pub type BadDebt<T: Config> = StorageMap<_, Blake2_128Concat, T::AssetId, T::Balance, ValueQuery>;
+
+    /// Storage layout version, used to migrate from older releases
+    #[pallet::storage]
+    pub(super) type StorageVersion<T> = StorageValue<_, Releases, ValueQuery>;
+
+    #[pallet::genesis_config]
+    pub struct GenesisConfig<T: Config> {
+        /// Assets registered at genesis with their rToken and its minimum balance, each rToken
+        /// created owned by the vault account
+        pub rtokens: Vec<(T::AssetId, T::AssetId, T::Balance)>,
+    }
+
+    #[cfg(feature = "std")]
+    impl<T: Config> Default for GenesisConfig<T> {
+        fn default() -> Self {
+            Self { rtokens: Vec::new() }
+        }
+    }
+
+    #[pallet::genesis_build]
+    impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+        fn build(&self) {
+            let vault = Pallet::<T>::account_id();
+            for (asset_id, r_asset_id, min_balance) in &self.rtokens {
+                Pallet::<T>::ensure_can_register(*asset_id, *r_asset_id).expect("invalid genesis rToken");
+                T::RTokenFactory::create(*r_asset_id, &vault, *min_balance).expect("genesis rToken creation failed");
+                RTokens::<T>::insert(asset_id, r_asset_id);
+            }
+            StorageVersion::<T>::put(Releases::V2_0_0);
+        }
+    }
+
+    #[pallet::event]
+    #[pallet::metadata(T::AccountId = "AccountId", T::AssetId = "AssetId", T::Balance = "Balance")]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
         /// Event emitted when a Backer deposits
-        VaultDeposit(AccountId, Balance),
+        VaultDeposit(T::AccountId, T::Balance),
         /// Event emitted when a Backer withdraws
-        VaultWithdraw(AccountId, Balance),
+        VaultWithdraw(T::AccountId, T::Balance),
+        /// Event emitted when the liquidator borrows from the vault [borrower, asset_id, amount]
+        Borrowed(T::AccountId, T::AssetId, T::Balance),
+        /// Event emitted when a borrower repays the vault [borrower, asset_id, amount]
+        Repaid(T::AccountId, T::AssetId, T::Balance),
+        /// Event emitted when an rToken owned by the vault is registered [asset_id, r_asset_id]
+        Registered(T::AssetId, T::AssetId),
+        /// Event emitted when the vault creates an rToken [asset_id, r_asset_id]
+        RTokenCreated(T::AssetId, T::AssetId),
+        /// Event emitted when a withdrawal waits for liquidity [who, asset_id, request_id, rtokens]
+        WithdrawalQueued(T::AccountId, T::AssetId, u64, T::Balance),
+        /// Event emitted when a queued withdrawal is partly paid [who, asset_id, request_id, rtokens_burnt, amount]
+        WithdrawalPartiallyFilled(T::AccountId, T::AssetId, u64, T::Balance, T::Balance),
+        /// Event emitted when a queued withdrawal is fully paid [who, asset_id, request_id, rtokens_burnt, amount]
+        WithdrawalCompleted(T::AccountId, T::AssetId, u64, T::Balance, T::Balance),
+        /// Event emitted when a queued withdrawal is cancelled [who, asset_id, request_id, rtokens_returned]
+        WithdrawalCancelled(T::AccountId, T::AssetId, u64, T::Balance),
+        /// Event emitted when a flash loan is repaid [borrower, asset_id, amount, fee]
+        FlashLoan(T::AccountId, T::AssetId, T::Balance, T::Balance),
+        /// Event emitted when an asset configuration is replaced [asset_id]
+        AssetConfigSet(T::AssetId),
+        /// Event emitted when an operation is paused or resumed [asset_id, operation, paused]
+        OperationPaused(T::AssetId, VaultOperation, bool),
+        /// Event emitted when an interest rate model is set [asset_id]
+        RateModelSet(T::AssetId),
+        /// Event emitted when interest is accrued [asset_id, interest, total_borrows]
+        InterestAccrued(T::AssetId, T::Balance, T::Balance),
+        /// Event emitted when debt is written off [asset_id, borrower, absorbed_by_reserves, residual_bad_debt]
+        BadDebtAbsorbed(T::AssetId, T::AccountId, T::Balance, T::Balance),
+        /// Event emitted when bad debt is charged to rToken holders [asset_id, amount, exchange_rate_before, exchange_rate_after]
+        BadDebtSocialized(T::AssetId, T::Balance, FixedU128, FixedU128),
+        /// Event emitted when the reserves of an asset are topped up [who, asset_id, amount]
+        ReservesFunded(T::AccountId, T::AssetId, T::Balance),
     }
-}
 
-// 5. Errors
-decl_error! {
-    pub enum Error for Module<T: Config> {
+    #[pallet::error]
+    pub enum Error<T> {
         /// Withdrawing more than previously deposited
         ExceedWithdrawAmount,
         /// Borrow called from a non-whitelisted account
@@ -64,110 +327,377 @@ decl_error! {
         NotRegistered,
         // Not enough r tokens to be burnt
         InsufficientSupply,
+        // Repaying more than the outstanding debt
+        ExceedRepayAmount,
+        // Interest rate model kink is above one
+        InvalidRateModel,
+        // Asset already has an rToken
+        AlreadyRegistered,
+        // rToken is the asset itself, already in use, already has a supply or is not owned by the vault
+        InvalidRToken,
+        // Deposits of this asset are paused
+        DepositPaused,
+        // Withdrawals of this asset are paused
+        WithdrawPaused,
+        // Borrows of this asset are paused
+        BorrowPaused,
+        // Deposit is below the asset minimum
+        BelowMinimumDeposit,
+        // Deposit would exceed the asset deposit cap
+        DepositCapExceeded,
+        // Borrow would exceed the asset borrow cap
+        BorrowCapExceeded,
+        // Vault underlying was not restored with the fee after a flash loan
+        FlashLoanNotRepaid,
+        // Deposits, withdrawals and reserve funding are not allowed from within a flash loan
+        FlashLoanInProgress,
+        // Too many pending withdrawals for this asset
+        WithdrawalQueueFull,
+        // Withdrawal request does not exist
+        UnknownWithdrawal,
+        // Withdrawal request belongs to another account
+        NotWithdrawalOwner,
+        // Transfer of the asset or rToken failed
+        TransferFailed,
+        // Minting rTokens failed
+        MintFailed,
+        // Burning rTokens failed
+        BurnFailed,
+        // Arithmetic overflow
+        Overflow,
+        // Socializing more than the recorded bad debt
+        ExceedBadDebt,
+        // Outstanding rTokens are worth nothing, so new deposits would be shared with their holders
+        ZeroExchangeRate,
     }
-}
 
-// 6. Callable Functions
-decl_module! {
-    pub struct Module<T: Config> for enum Call where origin: T::Origin {
-        // Errors must be initialized if they are used by the pallet.
-        type Error = Error<T>;
-
-        // Events must be initialized if they are used by the pallet.
-        fn deposit_event() = default;
-
-		const PalletId: PalletId = T::PalletId::get();
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn on_initialize(_n: T::BlockNumber) -> Weight {
+            Self::process_withdrawal_queues(T::WithdrawalQueueWeight::get())
+        }
 
-        const LiquidatorPalletId: PalletId = T::LiquidatorPalletId::get();
+        fn on_runtime_upgrade() -> Weight {
+            if StorageVersion::<T>::get() != Releases::V1_0_0 {
+                return 0;
+            }
+            // `decl_storage!` kept everything under the `Vault` prefix, FRAME v2 uses the runtime name
+            let name = <T as frame_system::Config>::PalletInfo::name::<Pallet<T>>().unwrap_or("Vault");
+            if name.as_bytes() != b"Vault" {
+                frame_support::storage::migration::move_pallet(b"Vault", name.as_bytes());
+            }
+            StorageVersion::<T>::put(Releases::V2_0_0);
+            T::BlockWeights::get().max_block
+        }
+    }
 
-        #[weight = 700_000]
-        pub fn vault_deposit(origin, asset_id: AssetId<T>, amount: BalanceOf<T>) {
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        #[pallet::weight(700_000)]
+        #[transactional]
+        pub fn vault_deposit(origin: OriginFor<T>, asset_id: T::AssetId, amount: T::Balance) -> DispatchResultWithPostInfo {
             // Check that the extrinsic was signed and get the signer.
             // This function will return an error if the extrinsic is not signed.
             // https://substrate.dev/docs/en/knowledgebase/runtime/origin
-            let sender_origin = origin.clone();
             let sender = ensure_signed(origin)?;
+            ensure!(!FlashLoanActive::<T>::get(), Error::<T>::FlashLoanInProgress);
 
-            // Clone sender variable to avoid giving ownership when using as a paramter
-            let origin_account = sender.clone();
+            Self::accrue_interest(asset_id);
             // Get the balance of the asset that belongs to the sender
-            let origin_balance = T::Currencies::balance(asset_id, &sender);
-            
+            let origin_balance = T::Currencies::free_balance(asset_id, &sender);
+
+            ensure!(RTokens::<T>::contains_key(asset_id), Error::<T>::NotRegistered);
             // Deposit amount cannot be zero
             ensure!(!amount.is_zero(), Error::<T>::ZeroAmount);
             // Balance cannot be less than deposit amount
             ensure!(origin_balance >= amount, Error::<T>::InsufficientBalance);
 
-            let mint_amount = Self::calculate_mint_amount(asset_id, amount);
-            // Self::deposit(origin, asset_id, amount);
+            let config = AssetConfigs::<T>::get(asset_id);
+            ensure!(!config.deposit_paused, Error::<T>::DepositPaused);
+            ensure!(amount >= config.min_deposit, Error::<T>::BelowMinimumDeposit);
+            if let Some(cap) = config.deposit_cap {
+                let total = Self::total_underlying(asset_id).checked_add(&amount).ok_or(Error::<T>::Overflow)?;
+                ensure!(total <= cap, Error::<T>::DepositCapExceeded);
+            }
+
+            // Price the deposit before the vault balance changes
+            let mint_amount = Self::calculate_mint_amount(asset_id, amount)?;
 
             // Deposit asset into vault
-            T::Currencies::transfer(asset_id, &sender, &Self::account_id(), amount, false);
+            T::Currencies::transfer(asset_id, &sender, &Self::account_id(), amount)
+                .map_err(|_| Error::<T>::TransferFailed)?;
 
             // Mint rTokens for user
             let mint_token = RTokens::<T>::get(asset_id);
-            T::Currencies::mint_into(mint_token, &sender, amount);
+            T::Currencies::mint(mint_token, &sender, mint_amount)
+                .map_err(|_| Error::<T>::MintFailed)?;
 
             // Emit an event that the deposit went through.
-            Self::deposit_event(RawEvent::VaultDeposit(origin_account, amount));
+            Self::deposit_event(Event::VaultDeposit(sender, amount));
+            Ok(().into())
         }
 
         // @param origin The user calling withdraw
         // @param asset_id The id of the withdraw token
         // @param amount The amount of r token to burn
-        #[weight = 700_000]
-        pub fn vault_withdraw(origin, asset_id: AssetId<T>, amount: BalanceOf<T>) {
+        #[pallet::weight(700_000)]
+        #[transactional]
+        pub fn vault_withdraw(origin: OriginFor<T>, asset_id: T::AssetId, amount: T::Balance) -> DispatchResultWithPostInfo {
             // Check that the extrinsic was signed and get the signer.
             // This function will return an error if the extrinsic is not signed.
             // https://substrate.dev/docs/en/knowledgebase/runtime/origin
             let sender = ensure_signed(origin)?;
-            let origin_account = sender.clone();
-            let origin_map = (asset_id, sender.clone());
+            ensure!(!FlashLoanActive::<T>::get(), Error::<T>::FlashLoanInProgress);
+            ensure!(RTokens::<T>::contains_key(asset_id), Error::<T>::NotRegistered);
+            ensure!(!AssetConfigs::<T>::get(asset_id).withdraw_paused, Error::<T>::WithdrawPaused);
+            Self::accrue_interest(asset_id);
 
-            let rtoken_total_supply: BalanceOf<T> = T::Currencies::total_issuance(RTokens::<T>::get(asset_id));
+            let rtoken = RTokens::<T>::get(asset_id);
+            let rtoken_total_supply = T::Currencies::total_issuance(rtoken);
             ensure!(!rtoken_total_supply.is_zero(), Error::<T>::InsufficientSupply);
-            // How much to withdraw based on burn amount
-            let withdraw_amount = Self::calculate_withdraw_amount(asset_id, amount, rtoken_total_supply);
             ensure!(!amount.is_zero(), Error::<T>::ZeroAmount);
+            ensure!(T::Currencies::free_balance(rtoken, &sender) >= amount, Error::<T>::ExceedWithdrawAmount);
+            // How much to withdraw based on burn amount
+            let withdraw_amount = Self::calculate_withdraw_amount(asset_id, amount)?;
+
+            // Wait behind earlier requests, or for repayments when the cash is lent out
+            let vault = Self::account_id();
+            let queue = WithdrawalQueue::<T>::get(asset_id);
+            if !queue.is_empty() || Self::available_liquidity(asset_id) < withdraw_amount {
+                Self::queue_withdrawal(sender, asset_id, amount, queue)?;
+                return Ok(().into());
+            }
 
             // Burn r token
-            T::Currencies::burn_from(RTokens::<T>::get(asset_id), &sender.clone(), amount);
+            T::Currencies::burn(rtoken, &sender, amount)
+                .map_err(|_| Error::<T>::BurnFailed)?;
 
-			T::Currencies::transfer(asset_id, &Self::account_id(), &sender, withdraw_amount, false);
+            T::Currencies::transfer(asset_id, &vault, &sender, withdraw_amount)
+                .map_err(|_| Error::<T>::TransferFailed)?;
 
             // Emit an event that the withdraw went through.
-            Self::deposit_event(RawEvent::VaultWithdraw(sender.clone(), amount));
+            Self::deposit_event(Event::VaultWithdraw(sender, amount));
+            Ok(().into())
         }
 
-        #[weight = 700_000]
-        pub fn borrow(origin, asset_id: AssetId<T>, amount: BalanceOf<T>) {
+        // Cancel a pending withdrawal and get the remaining rTokens back
+        #[pallet::weight(700_000)]
+        pub fn cancel_withdrawal(origin: OriginFor<T>, asset_id: T::AssetId, request_id: u64) -> DispatchResultWithPostInfo {
+            let sender = ensure_signed(origin)?;
+            let request = WithdrawalRequests::<T>::get(asset_id, request_id).ok_or(Error::<T>::UnknownWithdrawal)?;
+            ensure!(request.who == sender, Error::<T>::NotWithdrawalOwner);
+
+            T::Currencies::transfer(RTokens::<T>::get(asset_id), &Self::account_id(), &sender, request.rtokens)
+                .map_err(|_| Error::<T>::TransferFailed)?;
+
+            WithdrawalRequests::<T>::remove(asset_id, request_id);
+            Self::remove_from_queue(asset_id, request_id);
+
+            Self::deposit_event(Event::WithdrawalCancelled(sender, asset_id, request_id, request.rtokens));
+            Ok(().into())
+        }
+
+        #[pallet::weight(700_000)]
+        pub fn borrow(origin: OriginFor<T>, asset_id: T::AssetId, amount: T::Balance) -> DispatchResultWithPostInfo {
             // Check that the extrinsic was signed and get the signer.
             // This function will return an error if the extrinsic is not signed.
             // https://substrate.dev/docs/en/knowledgebase/runtime/origin
             let sender = ensure_signed(origin)?;
-            let origin_account = sender.clone();
-            let origin_balance = T::Currencies::balance(asset_id, &Self::account_id());
+            Self::do_borrow(&sender, asset_id, amount)?;
+            Ok(().into())
+        }
+
+        // @param origin The borrower repaying its debt
+        // @param asset_id The id of the borrowed token
+        // @param amount The amount of debt to repay
+        #[pallet::weight(700_000)]
+        pub fn repay(origin: OriginFor<T>, asset_id: T::AssetId, amount: T::Balance) -> DispatchResultWithPostInfo {
+            let sender = ensure_signed(origin)?;
+            Self::do_repay(&sender, asset_id, amount)?;
+            Ok(().into())
+        }
 
+        // Lend `amount` of the asset for the duration of `call`
+        //
+        // The underlying owed to rToken holders must be restored plus the flash loan fee once
+        // `call` returns, otherwise every change made by the loan is reverted. Borrows and
+        // repayments may happen within `call`, deposits and withdrawals may not.
+        #[pallet::weight({
+            let dispatch_info = call.get_dispatch_info();
+            dispatch_info.weight.saturating_add(700_000)
+        })]
+        #[transactional]
+        pub fn flash_loan(
+            origin: OriginFor<T>,
+            asset_id: T::AssetId,
+            amount: T::Balance,
+            call: Box<<T as Config>::Call>,
+        ) -> DispatchResultWithPostInfo {
+            let sender = ensure_signed(origin.clone())?;
+            ensure!(!FlashLoanActive::<T>::get(), Error::<T>::FlashLoanInProgress);
             ensure!(!amount.is_zero(), Error::<T>::ZeroAmount);
-            ensure!(origin_balance >= amount, Error::<T>::ExceedWithdrawAmount);
-            ensure!(origin_account == Self::liquidator_account_id(), Error::<T>::NotLiquidator);
-        
-            // Transfer asset to liquidator
-            // Self::withdraw(sender, asset_id, amount);
-			// <T::Currencies::Module<T>>::transfer(Self::account_id(), asset_id, Self::liquidator_account_id(), amount);
+            ensure!(RTokens::<T>::contains_key(asset_id), Error::<T>::NotRegistered);
+            ensure!(!AssetConfigs::<T>::get(asset_id).borrow_paused, Error::<T>::BorrowPaused);
+            Self::accrue_interest(asset_id);
+
+            let vault = Self::account_id();
+            ensure!(T::Currencies::free_balance(asset_id, &vault) >= amount, Error::<T>::ExceedWithdrawAmount);
+            let underlying_before = Self::total_underlying(asset_id);
+            let fee = T::FlashLoanFee::get().mul_ceil(amount);
+
+            FlashLoanActive::<T>::put(true);
+            T::Currencies::transfer(asset_id, &vault, &sender, amount)
+                .map_err(|_| Error::<T>::TransferFailed)?;
+            call.dispatch(origin).map_err(|e| e.error)?;
+            FlashLoanActive::<T>::kill();
+
+            // Repaying vault debt with the loan only turns borrows into cash, so borrows count too.
+            // The fee stays in the vault cash and raises the exchange rate for rToken holders.
+            let underlying_after = Self::total_underlying(asset_id);
+            ensure!(underlying_after >= underlying_before.saturating_add(fee), Error::<T>::FlashLoanNotRepaid);
+
+            Self::deposit_event(Event::FlashLoan(sender, asset_id, amount, fee));
+            Ok(().into())
         }
 
-        // Register new r token to asset
-        #[weight = 700_000]
-        pub fn register(origin, asset_id: AssetId<T>, r_asset_id: AssetId<T>) {
-            let sender = ensure_signed(origin)?;
+        // Register an existing rToken to asset
+        //
+        // The rToken must be owned by the vault, so that no other account can mint it.
+        #[pallet::weight(700_000)]
+        pub fn register(origin: OriginFor<T>, asset_id: T::AssetId, r_asset_id: T::AssetId) -> DispatchResultWithPostInfo {
+            T::RegisterOrigin::ensure_origin(origin)?;
+            Self::ensure_can_register(asset_id, r_asset_id)?;
+            ensure!(T::RTokenFactory::owner(r_asset_id) == Some(Self::account_id()), Error::<T>::InvalidRToken);
 
             RTokens::<T>::insert(asset_id, r_asset_id);
+
+            Self::deposit_event(Event::Registered(asset_id, r_asset_id));
+            Ok(().into())
+        }
+
+        // Create a new rToken owned by the vault and register it to asset
+        //
+        // The vault is the only account able to mint the rToken, so it cannot be pre-minted or
+        // minted later by a third party. The rToken metadata is derived from the asset by
+        // prefixing its name and symbol with "r".
+        #[pallet::weight(700_000)]
+        #[transactional]
+        pub fn create_rtoken(
+            origin: OriginFor<T>,
+            asset_id: T::AssetId,
+            r_asset_id: T::AssetId,
+            min_balance: T::Balance,
+        ) -> DispatchResultWithPostInfo {
+            T::RegisterOrigin::ensure_origin(origin)?;
+            Self::ensure_can_register(asset_id, r_asset_id)?;
+
+            let vault = Self::account_id();
+            T::RTokenFactory::create(r_asset_id, &vault, min_balance)?;
+
+            let (name, symbol, decimals) = T::RTokenFactory::metadata(asset_id);
+            let r_name = [&b"r"[..], &name[..]].concat();
+            let r_symbol = [&b"r"[..], &symbol[..]].concat();
+            T::RTokenFactory::set_metadata(r_asset_id, &vault, r_name, r_symbol, decimals)?;
+
+            RTokens::<T>::insert(asset_id, r_asset_id);
+
+            Self::deposit_event(Event::RTokenCreated(asset_id, r_asset_id));
+            Ok(().into())
+        }
+
+        // Set the interest rate model of a registered asset
+        #[pallet::weight(700_000)]
+        pub fn set_rate_model(origin: OriginFor<T>, asset_id: T::AssetId, model: InterestRateModel) -> DispatchResultWithPostInfo {
+            T::AdminOrigin::ensure_origin(origin)?;
+            ensure!(RTokens::<T>::contains_key(asset_id), Error::<T>::NotRegistered);
+            ensure!(model.is_valid(), Error::<T>::InvalidRateModel);
+
+            // Settle interest under the previous model before switching
+            Self::accrue_interest(asset_id);
+            RateModels::<T>::insert(asset_id, model);
+
+            Self::deposit_event(Event::RateModelSet(asset_id));
+            Ok(().into())
+        }
+
+        // Replace the caps, minimums and pause flags of a registered asset
+        #[pallet::weight(700_000)]
+        pub fn set_asset_config(
+            origin: OriginFor<T>,
+            asset_id: T::AssetId,
+            config: VaultAssetConfig<T::Balance>,
+        ) -> DispatchResultWithPostInfo {
+            T::AdminOrigin::ensure_origin(origin)?;
+            ensure!(RTokens::<T>::contains_key(asset_id), Error::<T>::NotRegistered);
+
+            // Interest accrued so far is reserved under the previous reserve factor
+            Self::accrue_interest(asset_id);
+            AssetConfigs::<T>::insert(asset_id, config);
+
+            Self::deposit_event(Event::AssetConfigSet(asset_id));
+            Ok(().into())
+        }
+
+        // Pause or resume a single operation of an asset
+        #[pallet::weight(700_000)]
+        pub fn set_paused(
+            origin: OriginFor<T>,
+            asset_id: T::AssetId,
+            operation: VaultOperation,
+            paused: bool,
+        ) -> DispatchResultWithPostInfo {
+            T::AdminOrigin::ensure_origin(origin)?;
+            ensure!(RTokens::<T>::contains_key(asset_id), Error::<T>::NotRegistered);
+
+            AssetConfigs::<T>::mutate(asset_id, |config| match operation {
+                VaultOperation::Deposit => config.deposit_paused = paused,
+                VaultOperation::Withdraw => config.withdraw_paused = paused,
+                VaultOperation::Borrow => config.borrow_paused = paused,
+            });
+
+            Self::deposit_event(Event::OperationPaused(asset_id, operation, paused));
+            Ok(().into())
+        }
+
+        // Add to the reserves that absorb bad debt before rToken holders do
+        #[pallet::weight(700_000)]
+        #[transactional]
+        pub fn fund_reserves(origin: OriginFor<T>, asset_id: T::AssetId, amount: T::Balance) -> DispatchResultWithPostInfo {
+            let sender = ensure_signed(origin)?;
+            ensure!(!FlashLoanActive::<T>::get(), Error::<T>::FlashLoanInProgress);
+            ensure!(RTokens::<T>::contains_key(asset_id), Error::<T>::NotRegistered);
+            ensure!(!amount.is_zero(), Error::<T>::ZeroAmount);
+            Self::accrue_interest(asset_id);
+
+            let reserves = TotalReserves::<T>::get(asset_id).checked_add(&amount).ok_or(Error::<T>::Overflow)?;
+            T::Currencies::transfer(asset_id, &sender, &Self::account_id(), amount)
+                .map_err(|_| Error::<T>::TransferFailed)?;
+            TotalReserves::<T>::insert(asset_id, reserves);
+
+            Self::deposit_event(Event::ReservesFunded(sender, asset_id, amount));
+            Ok(().into())
+        }
+
+        // Charge `amount` of bad debt to rToken holders by writing down the exchange rate
+        #[pallet::weight(700_000)]
+        pub fn socialize_bad_debt(origin: OriginFor<T>, asset_id: T::AssetId, amount: T::Balance) -> DispatchResultWithPostInfo {
+            T::AdminOrigin::ensure_origin(origin)?;
+            ensure!(!amount.is_zero(), Error::<T>::ZeroAmount);
+            ensure!(amount <= BadDebt::<T>::get(asset_id), Error::<T>::ExceedBadDebt);
+            Self::accrue_interest(asset_id);
+
+            let rate_before = Self::exchange_rate(asset_id);
+            BadDebt::<T>::mutate(asset_id, |bad_debt| *bad_debt -= amount);
+            TotalBorrows::<T>::mutate(asset_id, |total| *total = total.saturating_sub(amount));
+            let rate_after = Self::exchange_rate(asset_id);
+
+            Self::deposit_event(Event::BadDebtSocialized(asset_id, amount, rate_before, rate_after));
+            Ok(().into())
         }
     }
 }
 
-impl<T: Config> Module<T> {
+impl<T: Config> Pallet<T> {
 	// Add public immutables and private mutables.
 
 	/// The account ID of the vault.
@@ -182,19 +712,356 @@ impl<T: Config> Module<T> {
 		T::LiquidatorPalletId::get().into_account()
 	}
 
-    fn calculate_mint_amount(asset_id: AssetId<T>, amount: BalanceOf<T>) -> BalanceOf<T> {
-        let initial_balance: BalanceOf<T> = T::Currencies::balance(asset_id, &Self::account_id()) - amount;
-        let rtoken_total_supply: BalanceOf<T> = T::Currencies::total_issuance(RTokens::<T>::get(asset_id));
+    /// Fail unless `r_asset_id` may become the rToken of `asset_id`.
+    fn ensure_can_register(asset_id: T::AssetId, r_asset_id: T::AssetId) -> DispatchResult {
+        ensure!(!RTokens::<T>::contains_key(asset_id), Error::<T>::AlreadyRegistered);
+        ensure!(asset_id != r_asset_id && !RTokens::<T>::contains_key(r_asset_id), Error::<T>::InvalidRToken);
+        ensure!(!RTokens::<T>::iter_values().any(|r| r == r_asset_id), Error::<T>::InvalidRToken);
+        // Pre-minted rTokens could be redeemed against other depositors' funds
+        ensure!(T::Currencies::total_issuance(r_asset_id).is_zero(), Error::<T>::InvalidRToken);
+        Ok(())
+    }
+
+    /// Lend `amount` of the asset to the liquidator and record the debt.
+    #[transactional]
+    pub fn do_borrow(who: &T::AccountId, asset_id: T::AssetId, amount: T::Balance) -> DispatchResult {
+        Self::accrue_interest(asset_id);
+        ensure!(!amount.is_zero(), Error::<T>::ZeroAmount);
+        ensure!(Self::available_liquidity(asset_id) >= amount, Error::<T>::ExceedWithdrawAmount);
+        ensure!(*who == Self::liquidator_account_id(), Error::<T>::NotLiquidator);
+
+        let config = AssetConfigs::<T>::get(asset_id);
+        ensure!(!config.borrow_paused, Error::<T>::BorrowPaused);
+        if let Some(cap) = config.borrow_cap {
+            let total = TotalBorrows::<T>::get(asset_id).checked_add(&amount).ok_or(Error::<T>::Overflow)?;
+            ensure!(total <= cap, Error::<T>::BorrowCapExceeded);
+        }
+
+        // Record the outstanding debt
+        let debt = Self::borrow_balance(asset_id, who).checked_add(&amount).ok_or(Error::<T>::Overflow)?;
+        let total_borrows = TotalBorrows::<T>::get(asset_id).checked_add(&amount).ok_or(Error::<T>::Overflow)?;
+        Borrows::<T>::insert(asset_id, who, BorrowSnapshot {
+            principal: debt,
+            interest_index: Self::borrow_index(asset_id),
+        });
+        TotalBorrows::<T>::insert(asset_id, total_borrows);
+
+        // Transfer asset to liquidator
+        T::Currencies::transfer(asset_id, &Self::account_id(), who, amount)
+            .map_err(|_| Error::<T>::TransferFailed)?;
+
+        Self::deposit_event(Event::Borrowed(who.clone(), asset_id, amount));
+        Ok(())
+    }
+
+    /// Pay back `amount` of the debt of `who`.
+    #[transactional]
+    pub fn do_repay(who: &T::AccountId, asset_id: T::AssetId, amount: T::Balance) -> DispatchResult {
+        Self::accrue_interest(asset_id);
+        let debt = Self::borrow_balance(asset_id, who);
+
+        ensure!(!amount.is_zero(), Error::<T>::ZeroAmount);
+        ensure!(debt >= amount, Error::<T>::ExceedRepayAmount);
+
+        // Transfer asset back to the vault
+        T::Currencies::transfer(asset_id, who, &Self::account_id(), amount)
+            .map_err(|_| Error::<T>::TransferFailed)?;
+
+        // Clear the repaid debt
+        let remaining = debt - amount;
+        if remaining.is_zero() {
+            Borrows::<T>::remove(asset_id, who);
+        } else {
+            Borrows::<T>::insert(asset_id, who, BorrowSnapshot {
+                principal: remaining,
+                interest_index: Self::borrow_index(asset_id),
+            });
+        }
+        // Per borrower rounding can leave the total slightly below the sum of debts
+        TotalBorrows::<T>::mutate(asset_id, |total| *total = total.saturating_sub(amount));
+
+        Self::deposit_event(Event::Repaid(who.clone(), asset_id, amount));
+        Ok(())
+    }
+
+    /// Write off up to `amount` of the debt of `who` that will never be repaid.
+    ///
+    /// Reserves absorb the loss first. The rest is recorded as bad debt and keeps counting
+    /// towards the exchange rate until governance socializes it. Returns the amounts absorbed
+    /// by reserves and left as bad debt.
+    pub fn record_bad_debt(who: &T::AccountId, asset_id: T::AssetId, amount: T::Balance) -> (T::Balance, T::Balance) {
+        Self::accrue_interest(asset_id);
+        let debt = Self::borrow_balance(asset_id, who);
+        let amount = amount.min(debt);
+        if amount.is_zero() {
+            return (Zero::zero(), Zero::zero());
+        }
+
+        let remaining = debt - amount;
+        if remaining.is_zero() {
+            Borrows::<T>::remove(asset_id, who);
+        } else {
+            Borrows::<T>::insert(asset_id, who, BorrowSnapshot {
+                principal: remaining,
+                interest_index: Self::borrow_index(asset_id),
+            });
+        }
+
+        let absorbed = amount.min(TotalReserves::<T>::get(asset_id));
+        TotalReserves::<T>::mutate(asset_id, |reserves| *reserves -= absorbed);
+        TotalBorrows::<T>::mutate(asset_id, |total| *total = total.saturating_sub(absorbed));
+        let residual = amount - absorbed;
+        BadDebt::<T>::mutate(asset_id, |bad_debt| *bad_debt = bad_debt.saturating_add(residual));
+
+        Self::deposit_event(Event::BadDebtAbsorbed(asset_id, who.clone(), absorbed, residual));
+        (absorbed, residual)
+    }
+
+    /// Current borrow index of an asset.
+    pub fn borrow_index(asset_id: T::AssetId) -> FixedU128 {
+        BorrowIndex::<T>::get(asset_id).unwrap_or_else(FixedU128::one)
+    }
+
+    /// Outstanding debt of `who`, including interest accrued up to the last accrual.
+    pub fn borrow_balance(asset_id: T::AssetId, who: &T::AccountId) -> T::Balance {
+        let snapshot = Borrows::<T>::get(asset_id, who);
+        if snapshot.principal.is_zero() || snapshot.interest_index.is_zero() {
+            return snapshot.principal;
+        }
+        // debt = principal * borrow_index / interest_index
+        let growth = Self::borrow_index(asset_id) / snapshot.interest_index;
+        growth.saturating_mul_int(snapshot.principal.saturated_into::<u128>()).saturated_into()
+    }
+
+    /// Underlying owed to rToken holders: vault cash plus everything lent out, minus reserves.
+    pub fn total_underlying(asset_id: T::AssetId) -> T::Balance {
+        T::Currencies::free_balance(asset_id, &Self::account_id())
+            .saturating_add(TotalBorrows::<T>::get(asset_id))
+            .saturating_sub(TotalReserves::<T>::get(asset_id))
+    }
+
+    /// Underlying redeemable per rToken: `(cash + borrows - reserves) / rToken supply`.
+    ///
+    /// Starts at one while no rTokens exist.
+    pub fn exchange_rate(asset_id: T::AssetId) -> FixedU128 {
+        let rtoken_total_supply = T::Currencies::total_issuance(RTokens::<T>::get(asset_id));
         if rtoken_total_supply.is_zero() {
-            return amount;
+            return FixedU128::one();
+        }
+        FixedU128::checked_from_rational(
+            Self::total_underlying(asset_id).saturated_into::<u128>(),
+            rtoken_total_supply.saturated_into::<u128>(),
+        ).unwrap_or_else(FixedU128::one)
+    }
+
+    /// Accrue interest on the outstanding borrows of an asset up to the current block.
+    ///
+    /// Interest is simple over the elapsed blocks and compounds on every accrual.
+    pub fn accrue_interest(asset_id: T::AssetId) {
+        let now = <frame_system::Pallet<T>>::block_number();
+        let last = AccrualBlock::<T>::get(asset_id);
+        if now <= last {
+            return;
+        }
+        AccrualBlock::<T>::insert(asset_id, now);
+
+        let model = match RateModels::<T>::get(asset_id) {
+            Some(model) => model,
+            None => return,
+        };
+        let total_borrows = TotalBorrows::<T>::get(asset_id);
+        if total_borrows.is_zero() {
+            return;
         }
-        // mint_amount = amount_deposited * r_pool / pool
-        return amount * rtoken_total_supply / initial_balance;
+
+        // Bad debt waiting to be socialized does not earn interest
+        let borrows = total_borrows.saturating_sub(BadDebt::<T>::get(asset_id)).saturated_into::<u128>();
+        if borrows.is_zero() {
+            return;
+        }
+        let cash = T::Currencies::free_balance(asset_id, &Self::account_id()).saturated_into::<u128>();
+        let rate = model.borrow_rate(InterestRateModel::utilization(cash, borrows));
+        let blocks = FixedU128::saturating_from_integer((now - last).saturated_into::<u128>());
+        let factor = rate.saturating_mul(blocks);
+
+        let interest: T::Balance = factor.saturating_mul_int(borrows).saturated_into();
+        let new_total = total_borrows.saturating_add(interest);
+        let index = Self::borrow_index(asset_id);
+        BorrowIndex::<T>::insert(asset_id, index.saturating_add(index.saturating_mul(factor)));
+        TotalBorrows::<T>::insert(asset_id, new_total);
+
+        let reserved = AssetConfigs::<T>::get(asset_id).reserve_factor * interest;
+        TotalReserves::<T>::mutate(asset_id, |reserves| *reserves = reserves.saturating_add(reserved));
+
+        Self::deposit_event(Event::InterestAccrued(asset_id, interest, new_total));
+    }
+
+    /// Hold the rTokens of `who` in the vault and append a withdrawal request to the queue.
+    fn queue_withdrawal(who: T::AccountId, asset_id: T::AssetId, rtokens: T::Balance, mut queue: Vec<u64>) -> DispatchResult {
+        ensure!((queue.len() as u32) < T::MaxWithdrawalRequests::get(), Error::<T>::WithdrawalQueueFull);
+
+        T::Currencies::transfer(RTokens::<T>::get(asset_id), &who, &Self::account_id(), rtokens)
+            .map_err(|_| Error::<T>::TransferFailed)?;
+
+        let request_id = NextWithdrawalId::<T>::mutate(|id| {
+            let current = *id;
+            *id = id.wrapping_add(1);
+            current
+        });
+        WithdrawalRequests::<T>::insert(asset_id, request_id, WithdrawalRequest { who: who.clone(), rtokens });
+        queue.push(request_id);
+        WithdrawalQueue::<T>::insert(asset_id, queue);
+
+        Self::deposit_event(Event::WithdrawalQueued(who, asset_id, request_id, rtokens));
+        Ok(())
+    }
+
+    fn remove_from_queue(asset_id: T::AssetId, request_id: u64) {
+        WithdrawalQueue::<T>::mutate_exists(asset_id, |maybe_queue| {
+            if let Some(queue) = maybe_queue {
+                queue.retain(|id| *id != request_id);
+                if queue.is_empty() {
+                    *maybe_queue = None;
+                }
+            }
+        });
+    }
+
+    /// Fill queued withdrawals, oldest first, from the cash each asset has available.
+    ///
+    /// Stops once `budget` would be exceeded and returns the weight used.
+    fn process_withdrawal_queues(budget: Weight) -> Weight {
+        let per_request = T::DbWeight::get().reads_writes(6, 5);
+        let mut used: Weight = T::DbWeight::get().reads(1);
+        let assets: Vec<T::AssetId> = WithdrawalQueue::<T>::iter_keys().collect();
+
+        for asset_id in assets {
+            if used.saturating_add(per_request) > budget {
+                break;
+            }
+            // Paused withdrawals stay queued until they are resumed
+            used = used.saturating_add(T::DbWeight::get().reads(1));
+            if AssetConfigs::<T>::get(asset_id).withdraw_paused {
+                continue;
+            }
+            Self::accrue_interest(asset_id);
+            let rtoken = RTokens::<T>::get(asset_id);
+            let mut queue = WithdrawalQueue::<T>::get(asset_id);
+            let mut filled = 0;
+
+            for request_id in queue.iter() {
+                if used.saturating_add(per_request) > budget {
+                    break;
+                }
+                used = used.saturating_add(per_request);
+
+                let mut request = match WithdrawalRequests::<T>::get(asset_id, request_id) {
+                    Some(request) => request,
+                    None => {
+                        filled += 1;
+                        continue;
+                    }
+                };
+
+                // Redeem as many rTokens as the cash covers, rounding in favour of the vault
+                let cash = Self::available_liquidity(asset_id);
+                let burn = match Self::calculate_mint_amount(asset_id, cash) {
+                    Ok(burn) => burn.min(request.rtokens),
+                    Err(_) => break,
+                };
+                let amount = match Self::calculate_withdraw_amount(asset_id, burn) {
+                    Ok(amount) => amount,
+                    Err(_) => break,
+                };
+                if burn.is_zero() || amount.is_zero() {
+                    break;
+                }
+                if Self::redeem_held(rtoken, asset_id, &request.who, burn, amount).is_err() {
+                    break;
+                }
+
+                request.rtokens -= burn;
+                if request.rtokens.is_zero() {
+                    WithdrawalRequests::<T>::remove(asset_id, request_id);
+                    filled += 1;
+                    Self::deposit_event(Event::WithdrawalCompleted(request.who, asset_id, *request_id, burn, amount));
+                } else {
+                    Self::deposit_event(Event::WithdrawalPartiallyFilled(request.who.clone(), asset_id, *request_id, burn, amount));
+                    WithdrawalRequests::<T>::insert(asset_id, request_id, request);
+                    break;
+                }
+            }
+
+            queue.drain(..filled);
+            if queue.is_empty() {
+                WithdrawalQueue::<T>::remove(asset_id);
+            } else {
+                WithdrawalQueue::<T>::insert(asset_id, queue);
+            }
+        }
+
+        used
+    }
+
+    /// Burn rTokens held by the vault and pay the underlying to `who`, or do neither.
+    #[transactional]
+    fn redeem_held(rtoken: T::AssetId, asset_id: T::AssetId, who: &T::AccountId, burn: T::Balance, amount: T::Balance) -> DispatchResult {
+        let vault = Self::account_id();
+        T::Currencies::burn(rtoken, &vault, burn).map_err(|_| Error::<T>::BurnFailed)?;
+        T::Currencies::transfer(asset_id, &vault, who, amount).map_err(|_| Error::<T>::TransferFailed)?;
+        Ok(())
+    }
+
+    /// Underlying of an asset currently held by the vault.
+    pub fn total_cash(asset_id: T::AssetId) -> T::Balance {
+        T::Currencies::free_balance(asset_id, &Self::account_id())
+    }
+
+    /// Cash of an asset that may be withdrawn or lent out, leaving the reserves in the vault.
+    pub fn available_liquidity(asset_id: T::AssetId) -> T::Balance {
+        Self::total_cash(asset_id).saturating_sub(TotalReserves::<T>::get(asset_id))
+    }
+
+    /// Share of the underlying of an asset that is lent out.
+    pub fn utilization(asset_id: T::AssetId) -> FixedU128 {
+        InterestRateModel::utilization(
+            Self::total_cash(asset_id).saturated_into(),
+            TotalBorrows::<T>::get(asset_id).saturated_into(),
+        )
+    }
+
+    /// Underlying redeemable for the rTokens held by `who`.
+    pub fn underlying_balance(asset_id: T::AssetId, who: &T::AccountId) -> T::Balance {
+        let rtokens = T::Currencies::free_balance(RTokens::<T>::get(asset_id), who);
+        Self::calculate_withdraw_amount(asset_id, rtokens).unwrap_or_else(|_| Zero::zero())
+    }
+
+    /// rTokens minted for depositing `amount` of underlying, as of the last interest accrual.
+    pub fn quote_deposit(asset_id: T::AssetId, amount: T::Balance) -> T::Balance {
+        Self::calculate_mint_amount(asset_id, amount).unwrap_or_else(|_| Zero::zero())
+    }
+
+    /// Underlying paid out for burning `amount` of rTokens, as of the last interest accrual.
+    pub fn quote_withdraw(asset_id: T::AssetId, amount: T::Balance) -> T::Balance {
+        Self::calculate_withdraw_amount(asset_id, amount).unwrap_or_else(|_| Zero::zero())
+    }
+
+    fn calculate_mint_amount(asset_id: T::AssetId, amount: T::Balance) -> Result<T::Balance, Error<T>> {
+        let exchange_rate = Self::exchange_rate(asset_id);
+        ensure!(!exchange_rate.is_zero(), Error::<T>::ZeroExchangeRate);
+        // mint_amount = amount_deposited / exchange_rate
+        let minted = FixedU128::checked_from_integer(amount.saturated_into::<u128>())
+            .and_then(|amount| amount.checked_div(&exchange_rate))
+            .ok_or(Error::<T>::Overflow)?;
+        (minted.into_inner() / FixedU128::accuracy()).try_into().map_err(|_| Error::<T>::Overflow)
     }
 
-    fn calculate_withdraw_amount(asset_id: AssetId<T>, amount: BalanceOf<T>, rtoken_total_supply: BalanceOf<T>) -> BalanceOf<T> {
-        let initial_balance: BalanceOf<T> = T::Currencies::balance(asset_id, &Self::account_id());
-        // withdraw_amount = amount_to_burn * total / pool
-        return amount * initial_balance / rtoken_total_supply;
+    fn calculate_withdraw_amount(asset_id: T::AssetId, amount: T::Balance) -> Result<T::Balance, Error<T>> {
+        // withdraw_amount = amount_to_burn * exchange_rate
+        Self::exchange_rate(asset_id)
+            .checked_mul_int(amount.saturated_into::<u128>())
+            .ok_or(Error::<T>::Overflow)?
+            .try_into()
+            .map_err(|_| Error::<T>::Overflow)
     }
 }
@@ -1,14 +1,26 @@
-use crate as pallet_template;
+use crate as pallet_vault;
 use sp_core::H256;
-use frame_support::parameter_types;
+use frame_support::{dispatch::DispatchResult, parameter_types, PalletId, weights::Weight};
 use sp_runtime::{
-	traits::{BlakeTwo256, IdentityLookup}, testing::Header,
+	traits::{AccountIdConversion, BlakeTwo256, IdentityLookup}, testing::Header, Permill,
 };
 use frame_system as system;
 
 type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
 type Block = frame_system::mocking::MockBlock<Test>;
 
+pub const ALICE: u64 = 1;
+pub const BOB: u64 = 2;
+
+/// Underlying asset registered in the vault.
+pub const DOT: u32 = 0;
+/// rToken of `DOT`.
+pub const RDOT: u32 = 1;
+/// Id of the native currency among the vault currencies.
+pub const NATIVE: u32 = 100;
+/// rToken of `NATIVE`.
+pub const RNATIVE: u32 = 101;
+
 // Configure a mock runtime to test the pallet.
 frame_support::construct_runtime!(
 	pub enum Test where
@@ -17,10 +29,17 @@ frame_support::construct_runtime!(
 		UncheckedExtrinsic = UncheckedExtrinsic,
 	{
 		System: frame_system::{Module, Call, Config, Storage, Event<T>},
-		TemplateModule: pallet_template::{Module, Call, Storage, Event<T>},
+		Balances: pallet_balances::{Module, Call, Storage, Config<T>, Event<T>},
+		Assets: pallet_assets::{Module, Call, Storage, Event<T>},
+		Currencies: pallet_currencies::{Module, Storage},
+		Utility: pallet_utility::{Module, Call, Event},
+		VaultModule: pallet_vault::{Module, Call, Storage, Config<T>, Event<T>},
 	}
 );
 
+/// Named as in the runtime, so the pallet storage prefix differs from the `decl_storage!` one.
+pub type Vault = VaultModule;
+
 parameter_types! {
 	pub const BlockHashCount: u64 = 250;
 	pub const SS58Prefix: u8 = 42;
@@ -30,7 +49,7 @@ impl system::Config for Test {
 	type BaseCallFilter = ();
 	type BlockWeights = ();
 	type BlockLength = ();
-	type DbWeight = ();
+	type DbWeight = frame_support::weights::constants::RocksDbWeight;
 	type Origin = Origin;
 	type Call = Call;
 	type Index = u64;
@@ -44,18 +63,138 @@ impl system::Config for Test {
 	type BlockHashCount = BlockHashCount;
 	type Version = ();
 	type PalletInfo = PalletInfo;
-	type AccountData = ();
+	type AccountData = pallet_balances::AccountData<u64>;
 	type OnNewAccount = ();
 	type OnKilledAccount = ();
 	type SystemWeightInfo = ();
 	type SS58Prefix = SS58Prefix;
 }
 
-impl pallet_template::Config for Test {
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 1;
+	pub const MaxLocks: u32 = 50;
+}
+
+impl pallet_balances::Config for Test {
+	type MaxLocks = MaxLocks;
+	type Balance = u64;
+	type Event = Event;
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const AssetDeposit: u64 = 1;
+	pub const StringLimit: u32 = 50;
+	pub const MetadataDepositBase: u64 = 1;
+	pub const MetadataDepositPerByte: u64 = 1;
+}
+
+impl pallet_assets::Config for Test {
+	type Event = Event;
+	type Balance = u64;
+	type AssetId = u32;
+	type Currency = Balances;
+	type ForceOrigin = frame_system::EnsureRoot<u64>;
+	type MetadataDepositBase = MetadataDepositBase;
+	type MetadataDepositPerByte = MetadataDepositPerByte;
+	type StringLimit = StringLimit;
+	type AssetDepositBase = AssetDeposit;
+	type AssetDepositPerZombie = AssetDeposit;
+	type WeightInfo = ();
+}
+
+impl pallet_utility::Config for Test {
 	type Event = Event;
+	type Call = Call;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const CurrenciesPalletId: PalletId = PalletId(*b"rp/currs");
+	pub const NativeAssetId: u32 = NATIVE;
+}
+
+impl pallet_currencies::Config for Test {
+	type AssetId = u32;
+	type Balance = u64;
+	type Assets = pallet_vault::NativeAndAssets<Balances, Assets, NativeAssetId>;
+	type Decimals = ();
+	type PalletId = CurrenciesPalletId;
+	type MaxLocks = MaxLocks;
+}
+
+/// Creates rTokens in `pallet_assets`, leaving their metadata empty.
+pub struct AssetsRTokenFactory;
+
+impl pallet_vault::RTokenFactory<u64, u32, u64> for AssetsRTokenFactory {
+	fn create(id: u32, owner: &u64, min_balance: u64) -> DispatchResult {
+		Assets::force_create(Origin::root(), id, *owner, 10, min_balance)
+	}
+
+	fn metadata(_: u32) -> (Vec<u8>, Vec<u8>, u8) {
+		(Vec::new(), Vec::new(), 0)
+	}
+
+	fn set_metadata(_: u32, _: &u64, _: Vec<u8>, _: Vec<u8>, _: u8) -> DispatchResult {
+		Ok(())
+	}
+
+	fn owner(id: u32) -> Option<u64> {
+		pallet_assets::Asset::<Test>::get(id).map(|details| details.owner)
+	}
+}
+
+parameter_types! {
+	pub const VaultPalletId: PalletId = PalletId(*b"rp/vault");
+	pub const LiquidatorPalletId: PalletId = PalletId(*b"rp/liqtr");
+	pub const FlashLoanFee: Permill = Permill::from_percent(1);
+	pub const MaxWithdrawalRequests: u32 = 4;
+	pub const WithdrawalQueueWeight: Weight = 1_000_000_000;
+}
+
+impl pallet_vault::Config for Test {
+	type Event = Event;
+	type Balance = u64;
+	type AssetId = u32;
+	type PalletId = VaultPalletId;
+	type LiquidatorPalletId = LiquidatorPalletId;
+	type Currencies = Currencies;
+	type RegisterOrigin = frame_system::EnsureRoot<u64>;
+	type RTokenFactory = AssetsRTokenFactory;
+	type AdminOrigin = frame_system::EnsureRoot<u64>;
+	type Call = Call;
+	type FlashLoanFee = FlashLoanFee;
+	type MaxWithdrawalRequests = MaxWithdrawalRequests;
+	type WithdrawalQueueWeight = WithdrawalQueueWeight;
+}
+
+pub fn vault_account() -> u64 {
+	VaultPalletId::get().into_account()
+}
+
+pub fn liquidator_account() -> u64 {
+	LiquidatorPalletId::get().into_account()
 }
 
 // Build genesis storage according to the mock runtime.
 pub fn new_test_ext() -> sp_io::TestExternalities {
-	system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
+	let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(ALICE, 1_000), (BOB, 1_000), (liquidator_account(), 1_000)],
+	}.assimilate_storage(&mut t).unwrap();
+
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| {
+		System::set_block_number(1);
+		// ALICE owns the underlying, so she is also its freezer in the tests
+		Assets::force_create(Origin::root(), DOT, ALICE, 10, 1).unwrap();
+		Assets::mint(Origin::signed(ALICE), DOT, ALICE, 1_000).unwrap();
+		Assets::mint(Origin::signed(ALICE), DOT, BOB, 1_000).unwrap();
+		Assets::mint(Origin::signed(ALICE), DOT, liquidator_account(), 1_000).unwrap();
+		Vault::create_rtoken(Origin::root(), DOT, RDOT, 1).unwrap();
+	});
+	ext
 }
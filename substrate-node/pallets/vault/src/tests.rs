@@ -1,23 +1,795 @@
-use crate::{Error, mock::*};
-use frame_support::{assert_ok, assert_noop};
+use crate::{Borrows, Error, GenesisConfig, InterestRateModel, Releases, RTokens, StorageVersion, VaultAssetConfig, VaultOperation, mock::*};
+use sp_runtime::{traits::{One, Zero}, DispatchError, FixedPointNumber, FixedU128, Permill};
+use frame_support::{
+	assert_ok, assert_noop,
+	storage::migration::{get_storage_value, put_storage_value},
+	traits::{GenesisBuild, Get, OnInitialize, OnRuntimeUpgrade},
+	Blake2_128Concat, StorageHasher,
+};
+use codec::Encode;
+
+fn balance(asset: u32, who: u64) -> u64 {
+	Assets::balance(asset, who)
+}
+
+fn percent(rate: u128) -> FixedU128 {
+	FixedU128::saturating_from_rational(rate, 100)
+}
+
+/// 1% per block at zero utilization, 10% more per unit of utilization up to 80%, 100% above it.
+fn kinked_model() -> InterestRateModel {
+	InterestRateModel {
+		base_rate: percent(1),
+		slope1: percent(10),
+		kink: percent(80),
+		slope2: percent(100),
+	}
+}
+
+/// A flat 1% per block whatever the utilization.
+fn flat_model() -> InterestRateModel {
+	InterestRateModel { base_rate: percent(1), ..Default::default() }
+}
+
+#[test]
+fn deposit_mints_rtokens_at_initial_rate() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Vault::vault_deposit(Origin::signed(BOB), DOT, 100));
+		assert_eq!(balance(DOT, BOB), 900);
+		assert_eq!(balance(DOT, vault_account()), 100);
+		assert_eq!(balance(RDOT, BOB), 100);
+	});
+}
+
+#[test]
+fn rtokens_are_owned_by_the_vault() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Vault::create_rtoken(Origin::signed(ALICE), NATIVE, RNATIVE, 1),
+			DispatchError::BadOrigin
+		);
+		// An existing asset keeps its owner, who could mint rTokens at will
+		assert_ok!(Assets::force_create(Origin::root(), RNATIVE, ALICE, 10, 1));
+		assert_noop!(
+			Vault::create_rtoken(Origin::root(), NATIVE, RNATIVE, 1),
+			pallet_assets::Error::<Test>::InUse
+		);
+
+		assert_noop!(
+			Assets::mint(Origin::signed(ALICE), RDOT, ALICE, 100),
+			pallet_assets::Error::<Test>::NoPermission
+		);
+		assert_ok!(Vault::vault_deposit(Origin::signed(BOB), DOT, 100));
+		assert_eq!(balance(RDOT, BOB), 100);
+	});
+}
+
+#[test]
+fn register_requires_an_unused_rtoken_owned_by_the_vault() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Assets::force_create(Origin::root(), RNATIVE, ALICE, 10, 1));
+		assert_noop!(Vault::register(Origin::signed(ALICE), NATIVE, RNATIVE), DispatchError::BadOrigin);
+		assert_noop!(Vault::register(Origin::root(), NATIVE, RNATIVE), Error::<Test>::InvalidRToken);
+		assert_noop!(Vault::register(Origin::root(), DOT, 3), Error::<Test>::AlreadyRegistered);
+		assert_noop!(Vault::register(Origin::root(), NATIVE, RDOT), Error::<Test>::InvalidRToken);
+
+		assert_ok!(Assets::force_create(Origin::root(), 3, vault_account(), 10, 1));
+		assert_ok!(Vault::register(Origin::root(), NATIVE, 3));
+		assert_noop!(Vault::register(Origin::root(), NATIVE, 3), Error::<Test>::AlreadyRegistered);
+		assert_ok!(Vault::vault_deposit(Origin::signed(BOB), NATIVE, 100));
+		assert_eq!(balance(3, BOB), 100);
+	});
+}
+
+#[test]
+fn genesis_rtokens_are_created_owned_by_the_vault() {
+	new_test_ext().execute_with(|| {
+		GenesisBuild::<Test>::build(&GenesisConfig::<Test> { rtokens: vec![(NATIVE, 3, 1)] });
+		assert_eq!(RTokens::<Test>::get(NATIVE), 3);
+		assert_noop!(
+			Assets::mint(Origin::signed(ALICE), 3, ALICE, 100),
+			pallet_assets::Error::<Test>::NoPermission
+		);
+	});
+}
 
 #[test]
-fn it_works_for_default_value() {
+#[should_panic(expected = "invalid genesis rToken")]
+fn genesis_rejects_rtokens_in_use() {
 	new_test_ext().execute_with(|| {
-		// Dispatch a signed extrinsic.
-		assert_ok!(TemplateModule::do_something(Origin::signed(1), 42));
-		// Read pallet storage and assert an expected result.
-		assert_eq!(TemplateModule::something(), Some(42));
+		GenesisBuild::<Test>::build(&GenesisConfig::<Test> { rtokens: vec![(NATIVE, RDOT, 1)] });
 	});
 }
 
 #[test]
-fn correct_error_for_none_value() {
+fn deposit_rejects_unregistered_asset() {
 	new_test_ext().execute_with(|| {
-		// Ensure the expected error is thrown when no value is present.
 		assert_noop!(
-			TemplateModule::cause_error(Origin::signed(1)),
-			Error::<Test>::NoneValue
+			Vault::vault_deposit(Origin::signed(BOB), RDOT, 100),
+			Error::<Test>::NotRegistered
 		);
 	});
 }
+
+#[test]
+fn deposit_rejects_zero_and_excessive_amounts() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(Vault::vault_deposit(Origin::signed(BOB), DOT, 0), Error::<Test>::ZeroAmount);
+		assert_noop!(
+			Vault::vault_deposit(Origin::signed(BOB), DOT, 1_001),
+			Error::<Test>::InsufficientBalance
+		);
+	});
+}
+
+#[test]
+fn deposit_fails_atomically_when_transfer_fails() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Assets::freeze(Origin::signed(ALICE), DOT, BOB));
+		assert_noop!(
+			Vault::vault_deposit(Origin::signed(BOB), DOT, 100),
+			Error::<Test>::TransferFailed
+		);
+	});
+}
+
+#[test]
+fn deposit_fails_atomically_when_mint_fails() {
+	new_test_ext().execute_with(|| {
+		// The rToken of this asset cannot be minted below its minimum balance
+		assert_ok!(Assets::force_create(Origin::root(), 2, ALICE, 10, 1));
+		assert_ok!(Assets::mint(Origin::signed(ALICE), 2, BOB, 1_000));
+		assert_ok!(Vault::create_rtoken(Origin::root(), 2, 3, 1_000));
+
+		assert_noop!(
+			Vault::vault_deposit(Origin::signed(BOB), 2, 100),
+			Error::<Test>::MintFailed
+		);
+	});
+}
+
+#[test]
+fn deposit_overflow_is_reported() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Vault::vault_deposit(Origin::signed(BOB), DOT, 100));
+		assert_ok!(Vault::borrow(Origin::signed(liquidator_account()), DOT, 99));
+		Vault::record_bad_debt(&liquidator_account(), DOT, 99);
+		assert_ok!(Vault::socialize_bad_debt(Origin::root(), DOT, 99));
+		// One unit of underlying left for 100 rTokens
+		assert_eq!(Vault::exchange_rate(DOT), FixedU128::saturating_from_rational(1, 100));
+
+		let amount = u64::max_value() / 10;
+		assert_ok!(Assets::mint(Origin::signed(ALICE), DOT, BOB, amount));
+		assert_noop!(Vault::vault_deposit(Origin::signed(BOB), DOT, amount), Error::<Test>::Overflow);
+	});
+}
+
+#[test]
+fn withdraw_burns_rtokens_and_pays_underlying() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Vault::vault_deposit(Origin::signed(BOB), DOT, 100));
+		assert_ok!(Vault::vault_withdraw(Origin::signed(BOB), DOT, 40));
+		assert_eq!(balance(RDOT, BOB), 60);
+		assert_eq!(balance(DOT, BOB), 940);
+	});
+}
+
+/// BOB and ALICE back the vault with 100 DOT each, which the liquidator borrows entirely.
+fn lend_out_all_cash() {
+	assert_ok!(Vault::vault_deposit(Origin::signed(BOB), DOT, 100));
+	assert_ok!(Vault::vault_deposit(Origin::signed(ALICE), DOT, 100));
+	assert_ok!(Vault::borrow(Origin::signed(liquidator_account()), DOT, 200));
+}
+
+#[test]
+fn withdrawals_wait_for_liquidity_in_fifo_order() {
+	new_test_ext().execute_with(|| {
+		lend_out_all_cash();
+		assert_ok!(Vault::vault_withdraw(Origin::signed(BOB), DOT, 60));
+		assert_ok!(Vault::vault_withdraw(Origin::signed(ALICE), DOT, 100));
+		assert_eq!(Vault::withdrawal_queue(DOT), vec![0, 1]);
+		assert_eq!(balance(RDOT, vault_account()), 160);
+		assert!(System::events().iter().any(|record| record.event == Event::pallet_vault(crate::Event::WithdrawalQueued(BOB, DOT, 0, 60))));
+
+		// The oldest request is paid first
+		assert_ok!(Vault::repay(Origin::signed(liquidator_account()), DOT, 80));
+		Vault::on_initialize(2);
+		assert_eq!(balance(DOT, BOB), 960);
+		assert!(Vault::withdrawal_requests(DOT, 0).is_none());
+		assert_eq!(Vault::withdrawal_queue(DOT), vec![1]);
+
+		// The next one takes what is left and keeps its place
+		Vault::on_initialize(3);
+		assert_eq!(balance(DOT, ALICE), 920);
+		assert_eq!(Vault::withdrawal_requests(DOT, 1).map(|request| request.rtokens), Some(80));
+		assert!(System::events().iter().any(|record| record.event == Event::pallet_vault(crate::Event::WithdrawalPartiallyFilled(ALICE, DOT, 1, 20, 20))));
+
+		assert_ok!(Vault::repay(Origin::signed(liquidator_account()), DOT, 120));
+		Vault::on_initialize(4);
+		assert_eq!(balance(DOT, ALICE), 1_000);
+		assert!(Vault::withdrawal_queue(DOT).is_empty());
+		assert_eq!(balance(RDOT, vault_account()), 0);
+	});
+}
+
+#[test]
+fn queue_processing_stays_within_the_weight_budget() {
+	new_test_ext().execute_with(|| {
+		lend_out_all_cash();
+		assert_ok!(Vault::vault_withdraw(Origin::signed(BOB), DOT, 100));
+		assert_ok!(Vault::vault_withdraw(Origin::signed(ALICE), DOT, 100));
+		assert_ok!(Vault::repay(Origin::signed(liquidator_account()), DOT, 200));
+
+		// The budget only covers one request per block
+		let db = <Test as frame_system::Config>::DbWeight::get();
+		assert_eq!(Vault::on_initialize(2), db.reads(2) + db.reads_writes(6, 5));
+		assert_eq!(Vault::withdrawal_queue(DOT), vec![1]);
+		Vault::on_initialize(3);
+		assert!(Vault::withdrawal_queue(DOT).is_empty());
+		assert_eq!(balance(DOT, ALICE), 1_000);
+	});
+}
+
+#[test]
+fn paused_withdrawals_stay_queued() {
+	new_test_ext().execute_with(|| {
+		lend_out_all_cash();
+		assert_ok!(Vault::vault_withdraw(Origin::signed(BOB), DOT, 100));
+		assert_ok!(Vault::repay(Origin::signed(liquidator_account()), DOT, 200));
+		assert_ok!(Vault::set_paused(Origin::root(), DOT, VaultOperation::Withdraw, true));
+
+		Vault::on_initialize(2);
+		assert_eq!(Vault::withdrawal_queue(DOT), vec![0]);
+		assert_eq!(balance(DOT, BOB), 900);
+
+		assert_ok!(Vault::set_paused(Origin::root(), DOT, VaultOperation::Withdraw, false));
+		Vault::on_initialize(3);
+		assert!(Vault::withdrawal_queue(DOT).is_empty());
+		assert_eq!(balance(DOT, BOB), 1_000);
+	});
+}
+
+#[test]
+fn queued_withdrawals_are_cancelled_by_their_owner() {
+	new_test_ext().execute_with(|| {
+		lend_out_all_cash();
+		assert_ok!(Vault::vault_withdraw(Origin::signed(BOB), DOT, 60));
+		assert_eq!(balance(RDOT, BOB), 40);
+
+		assert_noop!(Vault::cancel_withdrawal(Origin::signed(BOB), DOT, 1), Error::<Test>::UnknownWithdrawal);
+		assert_noop!(Vault::cancel_withdrawal(Origin::signed(ALICE), DOT, 0), Error::<Test>::NotWithdrawalOwner);
+		assert_ok!(Vault::cancel_withdrawal(Origin::signed(BOB), DOT, 0));
+		assert_eq!(balance(RDOT, BOB), 100);
+		assert!(Vault::withdrawal_requests(DOT, 0).is_none());
+		assert!(Vault::withdrawal_queue(DOT).is_empty());
+		assert!(System::events().iter().any(|record| record.event == Event::pallet_vault(crate::Event::WithdrawalCancelled(BOB, DOT, 0, 60))));
+	});
+}
+
+#[test]
+fn withdrawal_queue_is_bounded() {
+	new_test_ext().execute_with(|| {
+		lend_out_all_cash();
+		for _ in 0..4 {
+			assert_ok!(Vault::vault_withdraw(Origin::signed(BOB), DOT, 10));
+		}
+		assert_noop!(Vault::vault_withdraw(Origin::signed(BOB), DOT, 10), Error::<Test>::WithdrawalQueueFull);
+	});
+}
+
+#[test]
+fn withdraw_rejects_more_than_held() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Vault::vault_deposit(Origin::signed(ALICE), DOT, 100));
+		assert_ok!(Vault::vault_deposit(Origin::signed(BOB), DOT, 100));
+		assert_noop!(
+			Vault::vault_withdraw(Origin::signed(BOB), DOT, 150),
+			Error::<Test>::ExceedWithdrawAmount
+		);
+	});
+}
+
+#[test]
+fn withdraw_fails_atomically_when_burn_fails() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Vault::vault_deposit(Origin::signed(BOB), DOT, 100));
+		assert_ok!(Assets::freeze(Origin::signed(vault_account()), RDOT, BOB));
+		assert_noop!(
+			Vault::vault_withdraw(Origin::signed(BOB), DOT, 50),
+			Error::<Test>::BurnFailed
+		);
+	});
+}
+
+#[test]
+fn withdraw_fails_atomically_when_transfer_fails() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Vault::vault_deposit(Origin::signed(BOB), DOT, 100));
+		assert_ok!(Assets::freeze(Origin::signed(ALICE), DOT, vault_account()));
+		assert_noop!(
+			Vault::vault_withdraw(Origin::signed(BOB), DOT, 50),
+			Error::<Test>::TransferFailed
+		);
+	});
+}
+
+#[test]
+fn borrow_is_restricted_to_the_liquidator() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Vault::vault_deposit(Origin::signed(BOB), DOT, 100));
+		assert_noop!(Vault::borrow(Origin::signed(BOB), DOT, 50), Error::<Test>::NotLiquidator);
+	});
+}
+
+#[test]
+fn borrow_and_repay_track_debt() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Vault::vault_deposit(Origin::signed(BOB), DOT, 100));
+		assert_ok!(Vault::borrow(Origin::signed(liquidator_account()), DOT, 60));
+		assert_eq!(Vault::borrow_balance(DOT, &liquidator_account()), 60);
+		assert_eq!(Vault::total_borrows(DOT), 60);
+		// Lent out funds still count towards the exchange rate
+		assert_eq!(Vault::quote_withdraw(DOT, 100), 100);
+
+		assert_noop!(
+			Vault::repay(Origin::signed(liquidator_account()), DOT, 61),
+			Error::<Test>::ExceedRepayAmount
+		);
+		assert_ok!(Vault::repay(Origin::signed(liquidator_account()), DOT, 60));
+		assert_eq!(Vault::borrow_balance(DOT, &liquidator_account()), 0);
+		assert_eq!(Vault::total_borrows(DOT), 0);
+	});
+}
+
+#[test]
+fn borrows_move_funds_and_partial_repayments_keep_the_rest_owed() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Vault::vault_deposit(Origin::signed(BOB), DOT, 100));
+		assert_noop!(Vault::borrow(Origin::signed(liquidator_account()), DOT, 0), Error::<Test>::ZeroAmount);
+		assert_noop!(
+			Vault::borrow(Origin::signed(liquidator_account()), DOT, 101),
+			Error::<Test>::ExceedWithdrawAmount
+		);
+		assert_noop!(
+			Vault::repay(Origin::signed(liquidator_account()), DOT, 1),
+			Error::<Test>::ExceedRepayAmount
+		);
+
+		// Debt of successive borrows adds up
+		assert_ok!(Vault::borrow(Origin::signed(liquidator_account()), DOT, 60));
+		assert_ok!(Vault::borrow(Origin::signed(liquidator_account()), DOT, 20));
+		assert_eq!(balance(DOT, vault_account()), 20);
+		assert_eq!(balance(DOT, liquidator_account()), 80);
+		assert_eq!(Vault::borrow_balance(DOT, &liquidator_account()), 80);
+		assert!(System::events().iter().any(|record| record.event == Event::pallet_vault(crate::Event::Borrowed(liquidator_account(), DOT, 20))));
+
+		assert_ok!(Vault::repay(Origin::signed(liquidator_account()), DOT, 30));
+		assert_eq!(balance(DOT, vault_account()), 50);
+		assert_eq!(Vault::borrow_balance(DOT, &liquidator_account()), 50);
+		assert_eq!(Vault::total_borrows(DOT), 50);
+		assert!(System::events().iter().any(|record| record.event == Event::pallet_vault(crate::Event::Repaid(liquidator_account(), DOT, 30))));
+
+		// Repaying the rest clears the loan
+		assert_ok!(Vault::repay(Origin::signed(liquidator_account()), DOT, 50));
+		assert!(!Borrows::<Test>::contains_key(DOT, liquidator_account()));
+		assert_eq!(balance(DOT, vault_account()), 100);
+		assert_eq!(balance(DOT, liquidator_account()), 0);
+	});
+}
+
+#[test]
+fn repay_fails_atomically_when_transfer_fails() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Vault::vault_deposit(Origin::signed(BOB), DOT, 100));
+		assert_ok!(Vault::borrow(Origin::signed(liquidator_account()), DOT, 60));
+		assert_ok!(Assets::freeze(Origin::signed(ALICE), DOT, liquidator_account()));
+		assert_noop!(
+			Vault::repay(Origin::signed(liquidator_account()), DOT, 60),
+			Error::<Test>::TransferFailed
+		);
+	});
+}
+
+#[test]
+fn flash_loan_must_be_repaid_with_fee() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Vault::vault_deposit(Origin::signed(ALICE), DOT, 500));
+
+		let keep = Box::new(Call::System(frame_system::Call::remark(vec![])));
+		assert_noop!(
+			Vault::flash_loan(Origin::signed(BOB), DOT, 100, keep),
+			Error::<Test>::FlashLoanNotRepaid
+		);
+
+		let repay = Box::new(Call::Assets(pallet_assets::Call::transfer(DOT, vault_account(), 101)));
+		assert_ok!(Vault::flash_loan(Origin::signed(BOB), DOT, 100, repay));
+		assert_eq!(balance(DOT, BOB), 999);
+		assert_eq!(balance(DOT, vault_account()), 501);
+	});
+}
+
+#[test]
+fn flash_loan_cannot_reenter_the_vault() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Vault::vault_deposit(Origin::signed(ALICE), DOT, 500));
+
+		let deposit = Box::new(Call::VaultModule(crate::Call::vault_deposit(DOT, 100)));
+		assert_noop!(
+			Vault::flash_loan(Origin::signed(BOB), DOT, 100, deposit),
+			Error::<Test>::FlashLoanInProgress
+		);
+	});
+}
+
+#[test]
+fn debt_can_be_repaid_inside_a_flash_loan() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Vault::vault_deposit(Origin::signed(ALICE), DOT, 500));
+		assert_ok!(Vault::borrow(Origin::signed(liquidator_account()), DOT, 60));
+		let batch = |returned| Box::new(Call::Utility(pallet_utility::Call::batch_all(vec![
+			Call::VaultModule(crate::Call::repay(DOT, 60)),
+			Call::Assets(pallet_assets::Call::transfer(DOT, vault_account(), returned)),
+		])));
+
+		// Repaying the debt with the loaned funds restores the cash but not the loan
+		assert_noop!(
+			Vault::flash_loan(Origin::signed(liquidator_account()), DOT, 100, batch(41)),
+			Error::<Test>::FlashLoanNotRepaid
+		);
+
+		assert_ok!(Vault::flash_loan(Origin::signed(liquidator_account()), DOT, 100, batch(101)));
+		assert_eq!(Vault::borrow_balance(DOT, &liquidator_account()), 0);
+		assert_eq!(balance(DOT, vault_account()), 501);
+		assert_eq!(balance(DOT, liquidator_account()), 999);
+	});
+}
+
+#[test]
+fn runtime_upgrade_moves_storage_from_the_old_prefix() {
+	new_test_ext().execute_with(|| {
+		// Written by the `decl_storage!` release under the `Vault` prefix
+		let key = Blake2_128Concat::hash(&NATIVE.encode());
+		put_storage_value(b"Vault", b"RTokens", &key, RNATIVE);
+		put_storage_value(b"Vault", b"TotalReserves", &key, 7u64);
+		assert_eq!(Vault::total_reserves(NATIVE), 0);
+		assert_eq!(StorageVersion::<Test>::get(), Releases::V1_0_0);
+
+		Vault::on_runtime_upgrade();
+		assert_eq!(StorageVersion::<Test>::get(), Releases::V2_0_0);
+		assert_eq!(get_storage_value::<u32>(b"Vault", b"RTokens", &key), None);
+		assert_eq!(get_storage_value::<u64>(b"Vault", b"TotalReserves", &key), None);
+		assert_eq!(RTokens::<Test>::get(NATIVE), RNATIVE);
+		assert_eq!(Vault::total_reserves(NATIVE), 7);
+		// Storage written under the new prefix is kept
+		assert_eq!(RTokens::<Test>::get(DOT), RDOT);
+
+		assert_eq!(Vault::on_runtime_upgrade(), 0);
+	});
+}
+
+#[test]
+fn bad_debt_is_absorbed_by_reserves_first() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Vault::vault_deposit(Origin::signed(BOB), DOT, 100));
+		assert_ok!(Vault::borrow(Origin::signed(liquidator_account()), DOT, 60));
+		assert_ok!(Vault::fund_reserves(Origin::signed(ALICE), DOT, 10));
+		assert_eq!(Vault::exchange_rate(DOT), FixedU128::one());
+
+		assert_eq!(Vault::record_bad_debt(&liquidator_account(), DOT, 30), (10, 20));
+		assert_eq!(Vault::borrow_balance(DOT, &liquidator_account()), 30);
+		assert_eq!(Vault::total_reserves(DOT), 0);
+		assert_eq!(Vault::bad_debt(DOT), 20);
+		// Residual bad debt does not touch rToken holders until it is socialized
+		assert_eq!(Vault::exchange_rate(DOT), FixedU128::one());
+	});
+}
+
+#[test]
+fn reserves_are_not_lent_out_or_withdrawn() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Vault::vault_deposit(Origin::signed(BOB), DOT, 100));
+		assert_ok!(Vault::fund_reserves(Origin::signed(ALICE), DOT, 10));
+		assert_eq!(Vault::available_liquidity(DOT), 100);
+
+		assert_noop!(Vault::borrow(Origin::signed(liquidator_account()), DOT, 101), Error::<Test>::ExceedWithdrawAmount);
+		assert_ok!(Vault::borrow(Origin::signed(liquidator_account()), DOT, 100));
+
+		// Only the reserves are left, so withdrawals wait for repayments
+		assert_ok!(Vault::vault_withdraw(Origin::signed(BOB), DOT, 10));
+		assert_eq!(Vault::withdrawal_queue(DOT), vec![0]);
+		Vault::on_initialize(2);
+		assert_eq!(Vault::withdrawal_queue(DOT), vec![0]);
+		assert_eq!(balance(DOT, vault_account()), 10);
+		assert_eq!(Vault::total_reserves(DOT), 10);
+	});
+}
+
+#[test]
+fn socializing_bad_debt_writes_down_the_exchange_rate() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Vault::vault_deposit(Origin::signed(BOB), DOT, 100));
+		assert_ok!(Vault::borrow(Origin::signed(liquidator_account()), DOT, 60));
+		Vault::record_bad_debt(&liquidator_account(), DOT, 20);
+
+		assert_noop!(Vault::socialize_bad_debt(Origin::root(), DOT, 21), Error::<Test>::ExceedBadDebt);
+		assert_ok!(Vault::socialize_bad_debt(Origin::root(), DOT, 20));
+		assert_eq!(Vault::bad_debt(DOT), 0);
+		assert_eq!(Vault::exchange_rate(DOT), FixedU128::saturating_from_rational(80, 100));
+		assert!(System::events().iter().any(|record| record.event == Event::pallet_vault(crate::Event::BadDebtSocialized(
+			DOT,
+			20,
+			FixedU128::one(),
+			FixedU128::saturating_from_rational(80, 100),
+		))));
+	});
+}
+
+#[test]
+fn native_currency_is_deposited_under_its_reserved_id() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(Vault::create_rtoken(Origin::root(), DOT, NATIVE, 1), Error::<Test>::AlreadyRegistered);
+		// The native currency has a supply, so it can never be an rToken
+		assert_noop!(Vault::create_rtoken(Origin::root(), RNATIVE, NATIVE, 1), Error::<Test>::InvalidRToken);
+		assert_ok!(Vault::create_rtoken(Origin::root(), NATIVE, RNATIVE, 1));
+
+		assert_ok!(Vault::vault_deposit(Origin::signed(BOB), NATIVE, 100));
+		assert_eq!(Balances::free_balance(BOB), 900);
+		assert_eq!(Balances::free_balance(vault_account()), 100);
+		assert_eq!(balance(RNATIVE, BOB), 100);
+		assert_noop!(
+			Vault::vault_deposit(Origin::signed(BOB), NATIVE, 901),
+			Error::<Test>::InsufficientBalance
+		);
+
+		assert_ok!(Vault::vault_withdraw(Origin::signed(BOB), NATIVE, 40));
+		assert_eq!(Balances::free_balance(BOB), 940);
+		assert_eq!(balance(RNATIVE, BOB), 60);
+	});
+}
+
+#[test]
+fn borrow_rate_follows_the_kinked_curve() {
+	let model = kinked_model();
+	assert_eq!(InterestRateModel::utilization(0, 0), FixedU128::zero());
+	assert_eq!(InterestRateModel::utilization(500, 500), percent(50));
+
+	assert_eq!(model.borrow_rate(FixedU128::zero()), percent(1));
+	// Below the kink only slope1 applies
+	assert_eq!(model.borrow_rate(percent(50)), percent(6));
+	assert_eq!(model.borrow_rate(percent(80)), percent(9));
+	// Above the kink the excess utilization is charged at slope2
+	assert_eq!(model.borrow_rate(percent(90)), percent(19));
+	assert_eq!(model.borrow_rate(FixedU128::one()), percent(29));
+}
+
+#[test]
+fn set_rate_model_is_validated() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Vault::set_rate_model(Origin::signed(ALICE), DOT, kinked_model()),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Vault::set_rate_model(Origin::root(), RDOT, kinked_model()),
+			Error::<Test>::NotRegistered
+		);
+		let model = InterestRateModel { kink: percent(150), ..kinked_model() };
+		assert_noop!(
+			Vault::set_rate_model(Origin::root(), DOT, model),
+			Error::<Test>::InvalidRateModel
+		);
+
+		assert_ok!(Vault::set_rate_model(Origin::root(), DOT, kinked_model()));
+		assert_eq!(Vault::rate_models(DOT), Some(kinked_model()));
+	});
+}
+
+#[test]
+fn interest_accrues_on_borrows_over_blocks() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Vault::set_rate_model(Origin::root(), DOT, flat_model()));
+		assert_ok!(Vault::vault_deposit(Origin::signed(BOB), DOT, 1_000));
+		assert_ok!(Vault::borrow(Origin::signed(liquidator_account()), DOT, 500));
+
+		// Nothing accrues within the block of the borrow
+		Vault::accrue_interest(DOT);
+		assert_eq!(Vault::total_borrows(DOT), 500);
+
+		System::set_block_number(11);
+		Vault::accrue_interest(DOT);
+		// 1% per block over 10 blocks
+		assert_eq!(Vault::total_borrows(DOT), 550);
+		assert_eq!(Vault::borrow_balance(DOT, &liquidator_account()), 550);
+		assert_eq!(Vault::borrow_index(DOT), percent(110));
+		assert_eq!(Vault::accrual_block(DOT), 11);
+		assert!(System::events().iter().any(|record| record.event == Event::pallet_vault(crate::Event::InterestAccrued(DOT, 50, 550))));
+
+		// Interest compounds on the next accrual
+		System::set_block_number(21);
+		Vault::accrue_interest(DOT);
+		assert_eq!(Vault::total_borrows(DOT), 605);
+		assert_eq!(Vault::borrow_balance(DOT, &liquidator_account()), 605);
+		// Depositors earn the interest through the exchange rate
+		assert_eq!(Vault::quote_withdraw(DOT, 1_000), 1_105);
+	});
+}
+
+#[test]
+fn rtokens_are_priced_at_the_exchange_rate_while_borrows_are_outstanding() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Vault::set_rate_model(Origin::root(), DOT, flat_model()));
+		assert_ok!(Vault::vault_deposit(Origin::signed(BOB), DOT, 1_000));
+		assert_ok!(Vault::borrow(Origin::signed(liquidator_account()), DOT, 500));
+
+		// The 50 of interest owed raises the underlying of each rToken to 1.05
+		System::set_block_number(11);
+		assert_ok!(Vault::vault_deposit(Origin::signed(ALICE), DOT, 105));
+		assert_eq!(Vault::exchange_rate(DOT), FixedU128::saturating_from_rational(105, 100));
+		assert_eq!(balance(RDOT, ALICE), 100);
+
+		assert_ok!(Vault::vault_withdraw(Origin::signed(ALICE), DOT, 100));
+		assert_eq!(balance(DOT, ALICE), 1_000);
+		assert_eq!(Vault::quote_withdraw(DOT, 1_000), 1_050);
+	});
+}
+
+#[test]
+fn api_quotes_match_deposits_and_withdrawals() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Vault::set_rate_model(Origin::root(), DOT, flat_model()));
+		assert_ok!(Vault::vault_deposit(Origin::signed(BOB), DOT, 1_000));
+		assert_ok!(Vault::borrow(Origin::signed(liquidator_account()), DOT, 500));
+		System::set_block_number(11);
+		Vault::accrue_interest(DOT);
+
+		assert_eq!(Vault::total_cash(DOT), 500);
+		assert_eq!(Vault::total_borrows(DOT), 550);
+		assert_eq!(Vault::utilization(DOT), FixedU128::saturating_from_rational(550, 1_050));
+		assert_eq!(Vault::underlying_balance(DOT, &BOB), 1_050);
+
+		let minted = Vault::quote_deposit(DOT, 105);
+		assert_eq!(minted, 100);
+		assert_ok!(Vault::vault_deposit(Origin::signed(ALICE), DOT, 105));
+		assert_eq!(balance(RDOT, ALICE), minted);
+
+		let paid = Vault::quote_withdraw(DOT, 40);
+		assert_eq!(paid, 42);
+		assert_ok!(Vault::vault_withdraw(Origin::signed(BOB), DOT, 40));
+		assert_eq!(balance(DOT, BOB), paid);
+		assert_eq!(Vault::underlying_balance(DOT, &BOB), 1_008);
+	});
+}
+
+#[test]
+fn deposits_are_rejected_once_rtokens_are_worth_nothing() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Vault::vault_deposit(Origin::signed(BOB), DOT, 100));
+		assert_ok!(Vault::borrow(Origin::signed(liquidator_account()), DOT, 100));
+		Vault::record_bad_debt(&liquidator_account(), DOT, 100);
+		assert_ok!(Vault::socialize_bad_debt(Origin::root(), DOT, 100));
+		assert!(Vault::exchange_rate(DOT).is_zero());
+
+		assert_noop!(Vault::vault_deposit(Origin::signed(ALICE), DOT, 100), Error::<Test>::ZeroExchangeRate);
+	});
+}
+
+#[test]
+fn reserve_factor_sets_aside_part_of_the_interest() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Vault::set_rate_model(Origin::root(), DOT, flat_model()));
+		assert_ok!(Vault::set_asset_config(Origin::root(), DOT, VaultAssetConfig {
+			reserve_factor: Permill::from_percent(10),
+			..Default::default()
+		}));
+		assert_ok!(Vault::vault_deposit(Origin::signed(BOB), DOT, 1_000));
+		assert_ok!(Vault::borrow(Origin::signed(liquidator_account()), DOT, 500));
+
+		System::set_block_number(11);
+		Vault::accrue_interest(DOT);
+		assert_eq!(Vault::total_borrows(DOT), 550);
+		// 10% of the 50 accrued goes to reserves, the rest to rToken holders
+		assert_eq!(Vault::total_reserves(DOT), 5);
+		assert_eq!(Vault::total_underlying(DOT), 1_045);
+		assert_eq!(Vault::exchange_rate(DOT), FixedU128::saturating_from_rational(1_045, 1_000));
+	});
+}
+
+#[test]
+fn asset_config_is_set_by_the_admin() {
+	new_test_ext().execute_with(|| {
+		let config = VaultAssetConfig { min_deposit: 10, ..Default::default() };
+		assert_noop!(
+			Vault::set_asset_config(Origin::signed(ALICE), DOT, config.clone()),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Vault::set_asset_config(Origin::root(), RDOT, config.clone()),
+			Error::<Test>::NotRegistered
+		);
+		assert_ok!(Vault::set_asset_config(Origin::root(), DOT, config.clone()));
+		assert_eq!(Vault::asset_config(DOT), config);
+
+		assert_noop!(
+			Vault::set_paused(Origin::signed(ALICE), DOT, VaultOperation::Deposit, true),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Vault::set_paused(Origin::root(), RDOT, VaultOperation::Deposit, true),
+			Error::<Test>::NotRegistered
+		);
+	});
+}
+
+#[test]
+fn deposits_respect_the_minimum_and_cap() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Vault::set_asset_config(Origin::root(), DOT, VaultAssetConfig {
+			deposit_cap: Some(150),
+			min_deposit: 10,
+			..Default::default()
+		}));
+
+		assert_noop!(Vault::vault_deposit(Origin::signed(BOB), DOT, 9), Error::<Test>::BelowMinimumDeposit);
+		assert_ok!(Vault::vault_deposit(Origin::signed(BOB), DOT, 100));
+		assert_noop!(Vault::vault_deposit(Origin::signed(ALICE), DOT, 51), Error::<Test>::DepositCapExceeded);
+		assert_ok!(Vault::vault_deposit(Origin::signed(ALICE), DOT, 50));
+		assert_eq!(Vault::total_underlying(DOT), 150);
+	});
+}
+
+#[test]
+fn borrows_respect_the_cap() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Vault::vault_deposit(Origin::signed(BOB), DOT, 100));
+		assert_ok!(Vault::set_asset_config(Origin::root(), DOT, VaultAssetConfig {
+			borrow_cap: Some(50),
+			..Default::default()
+		}));
+
+		assert_noop!(
+			Vault::borrow(Origin::signed(liquidator_account()), DOT, 51),
+			Error::<Test>::BorrowCapExceeded
+		);
+		assert_ok!(Vault::borrow(Origin::signed(liquidator_account()), DOT, 30));
+		assert_noop!(
+			Vault::borrow(Origin::signed(liquidator_account()), DOT, 21),
+			Error::<Test>::BorrowCapExceeded
+		);
+		assert_ok!(Vault::borrow(Origin::signed(liquidator_account()), DOT, 20));
+		assert_eq!(Vault::total_borrows(DOT), 50);
+	});
+}
+
+#[test]
+fn paused_operations_are_rejected() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Vault::vault_deposit(Origin::signed(BOB), DOT, 100));
+
+		assert_ok!(Vault::set_paused(Origin::root(), DOT, VaultOperation::Deposit, true));
+		assert!(System::events().iter().any(|record| record.event == Event::pallet_vault(crate::Event::OperationPaused(
+			DOT,
+			VaultOperation::Deposit,
+			true,
+		))));
+		assert_noop!(Vault::vault_deposit(Origin::signed(BOB), DOT, 100), Error::<Test>::DepositPaused);
+
+		assert_ok!(Vault::set_paused(Origin::root(), DOT, VaultOperation::Withdraw, true));
+		assert_noop!(Vault::vault_withdraw(Origin::signed(BOB), DOT, 50), Error::<Test>::WithdrawPaused);
+
+		assert_ok!(Vault::set_paused(Origin::root(), DOT, VaultOperation::Borrow, true));
+		assert_noop!(
+			Vault::borrow(Origin::signed(liquidator_account()), DOT, 50),
+			Error::<Test>::BorrowPaused
+		);
+		let repay = Box::new(Call::Assets(pallet_assets::Call::transfer(DOT, vault_account(), 51)));
+		assert_noop!(
+			Vault::flash_loan(Origin::signed(BOB), DOT, 50, repay),
+			Error::<Test>::BorrowPaused
+		);
+
+		// Each operation resumes on its own
+		assert_ok!(Vault::set_paused(Origin::root(), DOT, VaultOperation::Deposit, false));
+		assert_ok!(Vault::vault_deposit(Origin::signed(BOB), DOT, 100));
+		assert_noop!(Vault::vault_withdraw(Origin::signed(BOB), DOT, 50), Error::<Test>::WithdrawPaused);
+	});
+}
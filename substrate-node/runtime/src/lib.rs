@@ -10,11 +10,12 @@ use sp_std::prelude::*;
 use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
 use sp_runtime::{
 	ApplyExtrinsicResult, generic, create_runtime_str, impl_opaque_keys, MultiSignature,
-	transaction_validity::{TransactionValidity, TransactionSource},
+	transaction_validity::{TransactionPriority, TransactionValidity, TransactionSource}, SaturatedConversion,
 };
 use sp_runtime::traits::{
-	AccountIdLookup, BlakeTwo256, Block as BlockT, Verify, IdentifyAccount, NumberFor,
+	self, AccountIdLookup, BlakeTwo256, Block as BlockT, Verify, IdentifyAccount, NumberFor,
 };
+use codec::Encode;
 use sp_api::impl_runtime_apis;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use pallet_grandpa::{AuthorityId as GrandpaId, AuthorityList as GrandpaAuthorityList};
@@ -28,10 +29,10 @@ use sp_version::NativeVersion;
 pub use sp_runtime::BuildStorage;
 pub use pallet_timestamp::Call as TimestampCall;
 pub use pallet_balances::Call as BalancesCall;
-pub use sp_runtime::{Permill, Perbill};
+pub use sp_runtime::{Permill, Perbill, FixedU128};
 pub use frame_support::{
 	construct_runtime, parameter_types, StorageValue,
-	traits::{KeyOwnerProofSystem, Randomness},
+	traits::{Filter, KeyOwnerProofSystem, Randomness},
 	weights::{
 		Weight, IdentityFee,
 		constants::{BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight, WEIGHT_PER_SECOND},
@@ -59,6 +60,13 @@ pub type AccountIndex = u32;
 /// Balance of an account.
 pub type Balance = u128;
 
+/// Identifier of an asset in `pallet_assets`.
+pub type AssetId = u32;
+
+/// Balance of an account in `pallet_assets`, the native balance type so that the vault can hold
+/// both under one type.
+pub type AssetBalance = Balance;
+
 /// Index of a transaction in the chain.
 pub type Index = u32;
 
@@ -96,7 +104,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	spec_name: create_runtime_str!("node-template"),
 	impl_name: create_runtime_str!("node-template"),
 	authoring_version: 1,
-	spec_version: 100,
+	spec_version: 101,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
 	transaction_version: 1,
@@ -115,6 +123,9 @@ pub const SLOT_DURATION: u64 = MILLISECS_PER_BLOCK;
 
 pub const DOLLARS: u64 = 1;
 
+/// Decimals of the native currency, the default of substrate chains without token properties.
+pub const NATIVE_DECIMALS: u8 = 12;
+
 // Time is measured by number of blocks.
 pub const MINUTES: BlockNumber = 60_000 / (MILLISECS_PER_BLOCK as BlockNumber);
 pub const HOURS: BlockNumber = MINUTES * 60;
@@ -142,11 +153,24 @@ parameter_types! {
 	pub const SS58Prefix: u8 = 42;
 }
 
+/// Rejects creating the asset id the native currency is held under in `pallet_assets`, which
+/// would be unreachable next to the native currency.
+pub struct BaseFilter;
+
+impl Filter<Call> for BaseFilter {
+	fn filter(call: &Call) -> bool {
+		match call {
+			Call::Assets(pallet_assets::Call::create(id, ..)) => *id != NativeAssetId::get(),
+			_ => true,
+		}
+	}
+}
+
 // Configure FRAME pallets to include in runtime.
 
 impl frame_system::Config for Runtime {
 	/// The basic call filter to use in dispatchable.
-	type BaseCallFilter = ();
+	type BaseCallFilter = BaseFilter;
 	/// Block & extrinsics weights: base values and limits.
 	type BlockWeights = BlockWeights;
 	/// The maximum length of a block (in bytes).
@@ -255,8 +279,8 @@ parameter_types! {
 
 impl pallet_assets::Config for Runtime {
 	type Event = Event;
-	type Balance = u64;
-	type AssetId = u32;
+	type Balance = AssetBalance;
+	type AssetId = AssetId;
 	type Currency = Balances;
 	type ForceOrigin = frame_system::EnsureRoot<AccountId>;
 	// type AssetDeposit = AssetDeposit;
@@ -290,16 +314,281 @@ impl pallet_sudo::Config for Runtime {
 parameter_types! {
 	pub const VaultPalletId: PalletId = PalletId(*b"rp/vault");
 	pub const LiquidatorPalletId: PalletId = PalletId(*b"rp/liqtr");
+	pub const RTokenMaxZombies: u32 = 1_000;
+	pub const FlashLoanFee: Permill = Permill::from_parts(900);
+	pub const MaxWithdrawalRequests: u32 = 256;
+	/// Up to a tenth of the block is spent filling queued vault withdrawals.
+	pub WithdrawalQueueWeight: Weight = BlockWeights::get().max_block / 10;
+	/// Asset id the native currency is held under next to the assets, which `BaseFilter` and the
+	/// token factories keep from being created in `pallet_assets`.
+	pub const NativeAssetId: AssetId = AssetId::max_value();
+}
+
+parameter_types! {
+	pub const CurrenciesPalletId: PalletId = PalletId(*b"rp/currs");
+	pub const MaxCurrencyLocks: u32 = 16;
+}
+
+/// Reads asset decimals from the `pallet_assets` metadata, and those of the native currency.
+pub struct AssetsDecimals;
+
+impl pallet_currencies::Decimals<AssetId> for AssetsDecimals {
+	fn decimals(id: AssetId) -> u8 {
+		<AssetsRTokenFactory as pallet_vault::RTokenFactory<AccountId, AssetId, AssetBalance>>::metadata(id).2
+	}
+}
+
+impl pallet_currencies::Config for Runtime {
+	type AssetId = AssetId;
+	type Balance = AssetBalance;
+	type Assets = pallet_vault::NativeAndAssets<Balances, Assets, NativeAssetId>;
+	type Decimals = AssetsDecimals;
+	type PalletId = CurrenciesPalletId;
+	type MaxLocks = MaxCurrencyLocks;
+}
+
+/// Creates vault rTokens in `pallet_assets`.
+///
+/// The owner pays the metadata deposit, so the vault account must hold some native balance.
+pub struct AssetsRTokenFactory;
+
+impl pallet_vault::RTokenFactory<AccountId, AssetId, AssetBalance> for AssetsRTokenFactory {
+	fn create(id: AssetId, owner: &AccountId, min_balance: AssetBalance) -> frame_support::dispatch::DispatchResult {
+		frame_support::ensure!(id != NativeAssetId::get(), "the native asset id is reserved");
+		Assets::force_create(
+			frame_system::RawOrigin::Root.into(),
+			id.into(),
+			owner.clone().into(),
+			RTokenMaxZombies::get().into(),
+			min_balance.into(),
+		)
+	}
+
+	fn metadata(id: AssetId) -> (Vec<u8>, Vec<u8>, u8) {
+		if id == NativeAssetId::get() {
+			return (b"Native".to_vec(), b"UNIT".to_vec(), NATIVE_DECIMALS);
+		}
+		let metadata = pallet_assets::Metadata::<Runtime>::get(id);
+		(metadata.name, metadata.symbol, metadata.decimals)
+	}
+
+	fn set_metadata(
+		id: AssetId,
+		owner: &AccountId,
+		name: Vec<u8>,
+		symbol: Vec<u8>,
+		decimals: u8,
+	) -> frame_support::dispatch::DispatchResult {
+		Assets::set_metadata(
+			frame_system::RawOrigin::Signed(owner.clone()).into(),
+			id.into(),
+			name,
+			symbol,
+			decimals,
+		)
+	}
+
+	fn owner(id: AssetId) -> Option<AccountId> {
+		pallet_assets::Asset::<Runtime>::get(id).map(|details| details.owner)
+	}
 }
 
 /// Configure the template pallet in pallets/template.
 impl pallet_vault::Config for Runtime {
 	type Event = Event;
-	type Balance = u64;
-	type AssetId = u32;
-	type Currencies = Assets;
+	type Balance = AssetBalance;
+	type AssetId = AssetId;
+	type Currencies = Currencies;
 	type PalletId = VaultPalletId;
 	type LiquidatorPalletId = LiquidatorPalletId;
+	type RegisterOrigin = frame_system::EnsureRoot<AccountId>;
+	type RTokenFactory = AssetsRTokenFactory;
+	type AdminOrigin = frame_system::EnsureRoot<AccountId>;
+	type Call = Call;
+	type FlashLoanFee = FlashLoanFee;
+	type MaxWithdrawalRequests = MaxWithdrawalRequests;
+	type WithdrawalQueueWeight = WithdrawalQueueWeight;
+}
+
+parameter_types! {
+	pub const AmmPalletId: PalletId = PalletId(*b"rp/amm00");
+	pub const SwapFee: Permill = Permill::from_perthousand(3);
+	pub const MaxPathLength: u32 = 4;
+}
+
+/// Creates AMM LP tokens in `pallet_assets`.
+pub struct AssetsLpTokenFactory;
+
+impl pallet_amm::LpTokenFactory<AccountId, AssetId, AssetBalance> for AssetsLpTokenFactory {
+	fn create(id: AssetId, owner: &AccountId, min_balance: AssetBalance) -> frame_support::dispatch::DispatchResult {
+		frame_support::ensure!(id != NativeAssetId::get(), "the native asset id is reserved");
+		Assets::force_create(
+			frame_system::RawOrigin::Root.into(),
+			id.into(),
+			owner.clone().into(),
+			RTokenMaxZombies::get().into(),
+			min_balance.into(),
+		)
+	}
+}
+
+impl pallet_amm::Config for Runtime {
+	type Event = Event;
+	type Balance = AssetBalance;
+	type AssetId = AssetId;
+	type PalletId = AmmPalletId;
+	type Currencies = pallet_vault::NativeAndAssets<Balances, Assets, NativeAssetId>;
+	type CreateOrigin = frame_system::EnsureRoot<AccountId>;
+	type LpTokenFactory = AssetsLpTokenFactory;
+	type SwapFee = SwapFee;
+	type MaxPathLength = MaxPathLength;
+}
+
+/// Sells seized collateral directly into the AMM pool of the pair.
+pub struct AmmSwap;
+
+impl pallet_liquidator_adapter::Swap<AccountId, AssetId, AssetBalance> for AmmSwap {
+	fn quote_swap(
+		asset_in: AssetId,
+		asset_out: AssetId,
+		amount_in: AssetBalance,
+	) -> Result<AssetBalance, sp_runtime::DispatchError> {
+		Ok(Amm::quote_exact_in(&[asset_in, asset_out], amount_in)?)
+	}
+
+	fn swap(
+		who: &AccountId,
+		asset_in: AssetId,
+		asset_out: AssetId,
+		amount_in: AssetBalance,
+		min_out: AssetBalance,
+	) -> Result<AssetBalance, sp_runtime::DispatchError> {
+		Amm::do_swap_exact_in(who, vec![asset_in, asset_out], amount_in, min_out)
+	}
+}
+
+impl pallet_liquidator_adapter::Config for Runtime {
+	type Event = Event;
+	type AssetId = AssetId;
+	type Balance = AssetBalance;
+	type Currencies = Currencies;
+	type LiquidatorPalletId = LiquidatorPalletId;
+	type AdminOrigin = frame_system::EnsureRoot<AccountId>;
+	type Swap = AmmSwap;
+}
+
+parameter_types! {
+	pub const FinderBond: Balance = 1_000_000;
+	pub const MaxFinders: u32 = 64;
+	pub const FinderRewardShare: Permill = Permill::from_percent(50);
+	pub const AuctionStartPremium: Permill = Permill::from_percent(20);
+	pub const AuctionFloor: Permill = Permill::from_percent(50);
+	pub const AuctionDuration: BlockNumber = HOURS;
+	pub const MaxAuctions: u32 = 64;
+	pub const MaxBatchLiquidations: u32 = 32;
+	pub const MaxHistory: u32 = 10_000;
+	pub const MaxHistoryPerAccount: u32 = 100;
+	pub const KeeperInterval: BlockNumber = 5;
+	pub const MaxKeeperLiquidations: u32 = 16;
+	pub const MaxKeeperScan: u32 = 256;
+}
+
+impl pallet_liquidator::Config for Runtime {
+	type Event = Event;
+	type LiquidatorPalletId = LiquidatorPalletId;
+	type AdminOrigin = frame_system::EnsureRoot<AccountId>;
+	type BondCurrency = Balances;
+	type FinderBond = FinderBond;
+	type MaxFinders = MaxFinders;
+	type FinderRewardShare = FinderRewardShare;
+	type Slash = ();
+	type AuctionStartPremium = AuctionStartPremium;
+	type AuctionFloor = AuctionFloor;
+	type AuctionDuration = AuctionDuration;
+	type MaxAuctions = MaxAuctions;
+	type MaxBatchLiquidations = MaxBatchLiquidations;
+	type MaxHistory = MaxHistory;
+	type MaxHistoryPerAccount = MaxHistoryPerAccount;
+	type AuthorityId = pallet_liquidator::crypto::KeeperAuthId;
+	type KeeperInterval = KeeperInterval;
+	type MaxKeeperLiquidations = MaxKeeperLiquidations;
+	type MaxKeeperScan = MaxKeeperScan;
+	type PriceProvider = Oracle;
+}
+
+parameter_types! {
+	pub const MaxFeeders: u32 = 16;
+	pub const StalenessThreshold: BlockNumber = 10 * MINUTES;
+	pub const FetchInterval: BlockNumber = MINUTES;
+	pub const MaxPricesPerFeed: u32 = 32;
+	pub const OracleUnsignedPriority: TransactionPriority = TransactionPriority::max_value() / 2;
+	pub const MaxTwapWindow: BlockNumber = HOURS;
+	pub const TwapWindow: BlockNumber = 30 * MINUTES;
+	pub const MaxPriceDeviation: Permill = Permill::from_percent(15);
+}
+
+impl pallet_oracle::Config for Runtime {
+	type Event = Event;
+	type AssetId = AssetId;
+	type AdminOrigin = frame_system::EnsureRoot<AccountId>;
+	type Decimals = AssetsDecimals;
+	type MaxFeeders = MaxFeeders;
+	type StalenessThreshold = StalenessThreshold;
+	type AuthorityId = pallet_oracle::crypto::FeederAuthId;
+	type FetchInterval = FetchInterval;
+	type MaxPricesPerFeed = MaxPricesPerFeed;
+	type UnsignedPriority = OracleUnsignedPriority;
+	type MaxTwapWindow = MaxTwapWindow;
+	type TwapWindow = TwapWindow;
+	type MaxPriceDeviation = MaxPriceDeviation;
+}
+
+impl<LocalCall> frame_system::offchain::CreateSignedTransaction<LocalCall> for Runtime
+where
+	Call: From<LocalCall>,
+{
+	fn create_transaction<C: frame_system::offchain::AppCrypto<Self::Public, Self::Signature>>(
+		call: Call,
+		public: <Signature as traits::Verify>::Signer,
+		account: AccountId,
+		nonce: Index,
+	) -> Option<(Call, <UncheckedExtrinsic as traits::Extrinsic>::SignaturePayload)> {
+		let period = BlockHashCount::get()
+			.checked_next_power_of_two()
+			.map(|c| c / 2)
+			.unwrap_or(2) as u64;
+		let current_block = System::block_number()
+			.saturated_into::<u64>()
+			// The `System::block_number` is initialized with `n+1`,
+			// so the actual block number is `n`.
+			.saturating_sub(1);
+		let extra: SignedExtra = (
+			frame_system::CheckSpecVersion::<Runtime>::new(),
+			frame_system::CheckTxVersion::<Runtime>::new(),
+			frame_system::CheckGenesis::<Runtime>::new(),
+			frame_system::CheckEra::<Runtime>::from(generic::Era::mortal(period, current_block)),
+			frame_system::CheckNonce::<Runtime>::from(nonce),
+			frame_system::CheckWeight::<Runtime>::new(),
+			pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(0),
+		);
+		let raw_payload = SignedPayload::new(call, extra).ok()?;
+		let signature = raw_payload.using_encoded(|payload| C::sign(payload, public))?;
+		let (call, extra, _) = raw_payload.deconstruct();
+		Some((call, (sp_runtime::MultiAddress::Id(account), signature, extra)))
+	}
+}
+
+impl frame_system::offchain::SigningTypes for Runtime {
+	type Public = <Signature as traits::Verify>::Signer;
+	type Signature = Signature;
+}
+
+impl<C> frame_system::offchain::SendTransactionTypes<C> for Runtime
+where
+	Call: From<C>,
+{
+	type OverarchingCall = Call;
+	type Extrinsic = UncheckedExtrinsic;
 }
 
 // Create the runtime by composing the FRAME pallets that were previously configured.
@@ -318,8 +607,13 @@ construct_runtime!(
 		Balances: pallet_balances::{Module, Call, Storage, Config<T>, Event<T>},
 		TransactionPayment: pallet_transaction_payment::{Module, Storage},
 		Sudo: pallet_sudo::{Module, Call, Config<T>, Storage, Event<T>},
+		Currencies: pallet_currencies::{Module, Storage},
 		// Include the custom logic from the template pallet in the runtime.
-		VaultModule: pallet_vault::{Module, Call, Storage, Event<T>},
+		VaultModule: pallet_vault::{Module, Call, Storage, Config<T>, Event<T>},
+		LiquidatorAdapter: pallet_liquidator_adapter::{Module, Call, Storage, Config<T>, Event<T>},
+		Liquidator: pallet_liquidator::{Module, Call, Storage, Config<T>, Event<T>},
+		Amm: pallet_amm::{Module, Call, Storage, Event<T>},
+		Oracle: pallet_oracle::{Module, Call, Storage, Config<T>, Event<T>, ValidateUnsigned},
 	}
 );
 
@@ -345,6 +639,10 @@ pub type SignedExtra = (
 );
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
+/// A past liquidation as returned by the liquidator runtime API.
+pub type LiquidationRecord = pallet_liquidator::LiquidationRecordOf<Runtime>;
+/// The payload being signed in transactions.
+pub type SignedPayload = generic::SignedPayload<Call, SignedExtra>;
 /// Extrinsic type that has already been checked.
 pub type CheckedExtrinsic = generic::CheckedExtrinsic<AccountId, Call, SignedExtra>;
 /// Executive: handles dispatch to the various modules.
@@ -488,6 +786,46 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_vault_runtime_api::VaultApi<Block, AssetId, AccountId, AssetBalance> for Runtime {
+		fn exchange_rate(asset_id: AssetId) -> FixedU128 {
+			VaultModule::exchange_rate(asset_id)
+		}
+
+		fn total_cash(asset_id: AssetId) -> AssetBalance {
+			VaultModule::total_cash(asset_id)
+		}
+
+		fn total_borrows(asset_id: AssetId) -> AssetBalance {
+			VaultModule::total_borrows(asset_id)
+		}
+
+		fn utilization(asset_id: AssetId) -> FixedU128 {
+			VaultModule::utilization(asset_id)
+		}
+
+		fn underlying_balance(asset_id: AssetId, who: AccountId) -> AssetBalance {
+			VaultModule::underlying_balance(asset_id, &who)
+		}
+
+		fn quote_deposit(asset_id: AssetId, amount: AssetBalance) -> AssetBalance {
+			VaultModule::quote_deposit(asset_id, amount)
+		}
+
+		fn quote_withdraw(asset_id: AssetId, amount: AssetBalance) -> AssetBalance {
+			VaultModule::quote_withdraw(asset_id, amount)
+		}
+	}
+
+	impl pallet_liquidator_runtime_api::LiquidatorApi<Block, AccountId, LiquidationRecord> for Runtime {
+		fn target_history(who: AccountId, offset: u32, limit: u32) -> Vec<LiquidationRecord> {
+			Liquidator::target_history(&who, offset, limit)
+		}
+
+		fn finder_history(who: AccountId, offset: u32, limit: u32) -> Vec<LiquidationRecord> {
+			Liquidator::finder_history(&who, offset, limit)
+		}
+	}
+
 	#[cfg(feature = "runtime-benchmarks")]
 	impl frame_benchmarking::Benchmark<Block> for Runtime {
 		fn dispatch_benchmark(